@@ -0,0 +1,125 @@
+#[cfg(test)]
+mod tests;
+
+use std::error::Error as StdError;
+use std::fmt;
+use std::str;
+
+use protocol::cmd::Resp;
+
+// Longest verb we'll hang onto for `Error::InvalidCommand`; no real command
+// verb comes anywhere near this, so anything longer is simply truncated
+// rather than allocated (a raw opcode byte from the binary protocol fits
+// as-is).
+const MAX_VERB_LEN: usize = 16;
+
+// A command verb captured without allocating: the bytes read off the wire
+// before the parser gave up on them.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Verb {
+    bytes: [u8; MAX_VERB_LEN],
+    len: u8,
+}
+
+impl Verb {
+    pub fn new(bytes: &[u8]) -> Verb {
+        let len = bytes.len().min(MAX_VERB_LEN);
+        let mut buf = [0u8; MAX_VERB_LEN];
+        buf[..len].copy_from_slice(&bytes[..len]);
+        Verb {
+            bytes: buf,
+            len: len as u8,
+        }
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes[..self.len as usize]
+    }
+}
+
+impl fmt::Display for Verb {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match str::from_utf8(self.as_bytes()) {
+            Ok(s) => write!(f, "{}", s),
+            Err(_) => write!(f, "<non-utf8>"),
+        }
+    }
+}
+
+// Every failure mode that can surface while parsing a command off the wire
+// or applying it to the cache, unified into one type so a single `Result`
+// alias and a single `?` chain can cross both layers without a conversion
+// step in between.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Error {
+    Utf8,
+    NumberParse,
+    LineTooLong { limit: usize },
+    BadLineTerminator,
+    UnexpectedEof { expected: usize, got: usize },
+    StreamWrite,
+    InvalidCommand { verb: Verb },
+    CommandParse,
+    KeyTooLong { limit: u64, got: u64 },
+    ValueTooLong { limit: u64, got: u64 },
+    // Returned by `Cache::set` when the cache is configured with zero
+    // capacity, since it can never hold an entry and eviction has nothing
+    // to reclaim.
+    CapacityExceeded,
+    KeyNotFound,
+    CasMismatch,
+    NotStored,
+    NotNumeric,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Utf8 => write!(f, "invalid utf-8"),
+            Error::NumberParse => write!(f, "invalid number"),
+            Error::LineTooLong { limit } => {
+                write!(f, "line exceeds {} bytes without a terminator", limit)
+            }
+            Error::BadLineTerminator => write!(f, "line was not terminated with \\r\\n"),
+            Error::UnexpectedEof { expected, got } => {
+                write!(f, "unexpected eof: expected {} bytes, got {}", expected, got)
+            }
+            Error::StreamWrite => write!(f, "failed to write to the stream"),
+            Error::InvalidCommand { verb } => write!(f, "invalid command: {}", verb),
+            Error::CommandParse => write!(f, "malformed command"),
+            Error::KeyTooLong { limit, got } => {
+                write!(f, "key exceeds maximum length of {} bytes (got {})", limit, got)
+            }
+            Error::ValueTooLong { limit, got } => {
+                write!(f, "value exceeds maximum length of {} bytes (got {})", limit, got)
+            }
+            Error::CapacityExceeded => write!(f, "cache is at capacity"),
+            Error::KeyNotFound => write!(f, "key not found"),
+            Error::CasMismatch => write!(f, "cas token does not match the stored value"),
+            Error::NotStored => write!(f, "item was not stored"),
+            Error::NotNumeric => write!(f, "value is not numeric"),
+        }
+    }
+}
+
+impl StdError for Error {}
+
+impl Error {
+    // Maps this error onto the protocol response it should produce,
+    // preferring memcached's own precise response kinds (`NotFound`,
+    // `NotStored`, `Exists`, `NotNumeric`) over a generic error line wherever
+    // one exists, and otherwise telling CLIENT_ERROR (malformed or oversized
+    // input) apart from SERVER_ERROR (this side of the connection failed).
+    pub fn to_resp(self) -> Resp {
+        match self {
+            Error::KeyNotFound => Resp::NotFound,
+            Error::NotStored => Resp::NotStored,
+            Error::CasMismatch => Resp::Exists,
+            Error::NotNumeric => Resp::NotNumeric,
+            Error::UnexpectedEof { .. } | Error::StreamWrite | Error::CapacityExceeded => {
+                Resp::ServerError(self.to_string())
+            }
+            _ => Resp::ClientError(self.to_string()),
+        }
+    }
+}
@@ -0,0 +1,48 @@
+use protocol::cmd::Resp;
+use super::Error;
+use super::Verb;
+
+#[test]
+fn test_to_resp_maps_precise_cache_outcomes() {
+    assert_eq!(Error::KeyNotFound.to_resp(), Resp::NotFound);
+    assert_eq!(Error::NotStored.to_resp(), Resp::NotStored);
+    assert_eq!(Error::CasMismatch.to_resp(), Resp::Exists);
+    assert_eq!(Error::NotNumeric.to_resp(), Resp::NotNumeric);
+}
+
+#[test]
+fn test_to_resp_maps_server_side_failures_to_server_error() {
+    let errs = [
+        Error::UnexpectedEof { expected: 4, got: 1 },
+        Error::StreamWrite,
+        Error::CapacityExceeded,
+    ];
+
+    for err in errs.iter() {
+        match err.to_resp() {
+            Resp::ServerError(ref message) => assert_eq!(*message, err.to_string()),
+            other => panic!("expected Resp::ServerError for {:?}, got {:?}", err, other),
+        }
+    }
+}
+
+#[test]
+fn test_to_resp_maps_malformed_or_oversized_input_to_client_error() {
+    let errs = [
+        Error::Utf8,
+        Error::NumberParse,
+        Error::LineTooLong { limit: 1024 },
+        Error::BadLineTerminator,
+        Error::InvalidCommand { verb: Verb::new(b"bogus") },
+        Error::CommandParse,
+        Error::KeyTooLong { limit: 250, got: 300 },
+        Error::ValueTooLong { limit: 1024, got: 2048 },
+    ];
+
+    for err in errs.iter() {
+        match err.to_resp() {
+            Resp::ClientError(ref message) => assert_eq!(*message, err.to_string()),
+            other => panic!("expected Resp::ClientError for {:?}, got {:?}", err, other),
+        }
+    }
+}
@@ -0,0 +1,8 @@
+mod error;
+mod protocol;
+mod storage;
+mod tcp_transport;
+
+fn main() {
+    println!("emcache");
+}
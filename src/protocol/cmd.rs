@@ -0,0 +1,193 @@
+#[derive(Debug, PartialEq, Clone)]
+pub struct Get {
+    pub key: String,
+    pub with_cas: bool, // true for "gets", which echoes the CAS token back
+}
+
+impl Get {
+    pub fn new<S: Into<String>>(key: S) -> Get {
+        Get {
+            key: key.into(),
+            with_cas: false,
+        }
+    }
+
+    pub fn gets<S: Into<String>>(key: S) -> Get {
+        Get {
+            key: key.into(),
+            with_cas: true,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Set {
+    pub key: String,
+    pub flags: u32,
+    pub exptime: f64,
+    pub data: Vec<u8>,
+}
+
+impl Set {
+    pub fn new<S: Into<String>>(key: S, flags: u32, data: Vec<u8>) -> Set {
+        Set {
+            key: key.into(),
+            flags: flags,
+            exptime: 0.0,
+            data: data,
+        }
+    }
+
+    pub fn with_exptime(&mut self, exptime: f64) -> &mut Set {
+        self.exptime = exptime;
+        self
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Cas {
+    pub key: String,
+    pub flags: u32,
+    pub exptime: f64,
+    pub data: Vec<u8>,
+    pub cas_unique: u64,
+}
+
+impl Cas {
+    pub fn new<S: Into<String>>(key: S, flags: u32, data: Vec<u8>, cas_unique: u64) -> Cas {
+        Cas {
+            key: key.into(),
+            flags: flags,
+            exptime: 0.0,
+            data: data,
+            cas_unique: cas_unique,
+        }
+    }
+
+    pub fn with_exptime(&mut self, exptime: f64) -> &mut Cas {
+        self.exptime = exptime;
+        self
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Delete {
+    pub key: String,
+}
+
+impl Delete {
+    pub fn new<S: Into<String>>(key: S) -> Delete {
+        Delete { key: key.into() }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct IncrDecr {
+    pub key: String,
+    pub delta: u64,
+}
+
+impl IncrDecr {
+    pub fn new<S: Into<String>>(key: S, delta: u64) -> IncrDecr {
+        IncrDecr {
+            key: key.into(),
+            delta: delta,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct FlushAll {
+    pub delay: f64,
+}
+
+impl FlushAll {
+    pub fn new() -> FlushAll {
+        FlushAll { delay: 0.0 }
+    }
+
+    pub fn with_delay(&mut self, delay: f64) -> &mut FlushAll {
+        self.delay = delay;
+        self
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Cmd {
+    Get(Get),
+    Set(Set),
+    Cas(Cas),
+    // `add`/`replace` share `set`'s wire format and fields.
+    Add(Set),
+    Replace(Set),
+    // `append`/`prepend` also share it; their flags/exptime are ignored in
+    // favor of whatever the existing stored value already has.
+    Append(Set),
+    Prepend(Set),
+    Delete(Delete),
+    Incr(IncrDecr),
+    Decr(IncrDecr),
+    FlushAll(FlushAll),
+    Stats,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Value {
+    pub key: String,
+    pub flags: u32,
+    pub data: Vec<u8>,
+    pub cas_unique: Option<u64>, // Some(..) only for "gets" responses
+}
+
+impl Value {
+    pub fn new<S: Into<String>>(key: S, data: Vec<u8>) -> Value {
+        Value {
+            key: key.into(),
+            flags: 0,
+            data: data,
+            cas_unique: None,
+        }
+    }
+
+    pub fn with_flags(&mut self, flags: u32) -> &mut Value {
+        self.flags = flags;
+        self
+    }
+
+    pub fn with_cas_unique(&mut self, cas_unique: u64) -> &mut Value {
+        self.cas_unique = Some(cas_unique);
+        self
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Stat {
+    pub name: String,
+    pub value: String,
+}
+
+impl Stat {
+    pub fn new<S: Into<String>>(name: S, value: String) -> Stat {
+        Stat {
+            name: name.into(),
+            value: value,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Resp {
+    Error,
+    Stored,
+    Exists,
+    NotFound,
+    NotStored,
+    Deleted,
+    Number(u64),
+    ClientError(String),
+    NotNumeric,
+    ServerError(String),
+    Ok,
+    Value(Value),
+    Stats(Vec<Stat>),
+}
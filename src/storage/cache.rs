@@ -1,7 +1,12 @@
+use std::collections::BTreeMap;
 use std::collections::HashMap;
+use std::str;
 
-use super::errors::CacheError;
+use protocol::cmd::Stat;
+
+use error::Error;
 use super::key::Key;
+use super::stats::Stats;
 use super::typedefs::CacheResult;
 use super::utils::time_now_utc;
 use super::value::Value;
@@ -13,6 +18,11 @@ pub struct Cache {
     key_maxlen: u64, // in bytes
     value_maxlen: u64, // in bytes
     storage: HashMap<Key, Value>,
+    recency: BTreeMap<u64, Key>, // access counter -> key, oldest first
+    recency_of: HashMap<Key, u64>, // key -> its current slot in `recency`
+    next_recency: u64,
+    next_cas: u64,
+    stats: Stats,
 }
 
 impl Cache {
@@ -23,6 +33,11 @@ impl Cache {
             key_maxlen: 250, // 250b
             value_maxlen: 1048576, // 1mb
             storage: HashMap::new(),
+            recency: BTreeMap::new(),
+            recency_of: HashMap::new(),
+            next_recency: 0,
+            next_cas: 0,
+            stats: Stats::new(),
         }
     }
 
@@ -42,15 +57,28 @@ impl Cache {
     }
 
 
-    fn check_key_len(&self, key: &Key) -> bool {
-        key.len() as u64 <= self.key_maxlen
+    fn check_key_len(&self, key: &Key) -> CacheResult<()> {
+        let got = key.len() as u64;
+        if got <= self.key_maxlen {
+            Ok(())
+        } else {
+            Err(Error::KeyTooLong { limit: self.key_maxlen, got: got })
+        }
     }
 
-    fn check_value_len(&self, value: &Value) -> bool {
-        value.len() as u64 <= self.value_maxlen
+    fn check_value_len(&self, value: &Value) -> CacheResult<()> {
+        let got = value.len() as u64;
+        if got <= self.value_maxlen {
+            Ok(())
+        } else {
+            Err(Error::ValueTooLong { limit: self.value_maxlen, got: got })
+        }
     }
 
-    fn value_is_alive(&self, value: &Value) -> bool {
+    // Free function rather than a `&self` method so it can be called while
+    // `self.storage` is already borrowed (e.g. while scanning for an
+    // expired eviction victim).
+    fn value_is_alive(value: &Value, item_lifetime: f64) -> bool {
         // if the value has an exptime set, that takes precedence
         if value.exptime > 0.0 {
             if value.exptime > time_now_utc() {
@@ -61,23 +89,94 @@ impl Cache {
         }
 
         // if we have no lifetime setting then values are always live
-        if self.item_lifetime < 0.0 {
+        if item_lifetime < 0.0 {
             return true;
         }
 
         // otherwise use lifetime to determine liveness
-        value.atime + self.item_lifetime > time_now_utc()
+        value.atime + item_lifetime > time_now_utc()
     }
 
     fn remove(&mut self, key: &Key) -> CacheResult<()> {
         let opt = self.storage.remove(key);
 
+        if let Some(seq) = self.recency_of.remove(key) {
+            self.recency.remove(&seq);
+        }
+
         match opt {
-            Some(_) => Ok(()),
-            None => Err(CacheError::KeyNotFound),
+            Some(value) => {
+                self.stats.record_remove(value.len() as u64);
+                Ok(())
+            }
+            None => Err(Error::KeyNotFound),
+        }
+    }
+
+    // Hands out a fresh CAS token, stamped onto a value every time it's
+    // stored so `cas()` can later detect whether it changed underneath a
+    // client holding an older token.
+    fn next_cas_id(&mut self) -> u64 {
+        let cas_id = self.next_cas;
+        self.next_cas += 1;
+        cas_id
+    }
+
+    // Records that `key` was just read or written, moving it to the most
+    // recently used end of the LRU ordering.
+    fn touch_recency(&mut self, key: &Key) {
+        if let Some(old_seq) = self.recency_of.remove(key) {
+            self.recency.remove(&old_seq);
+        }
+
+        let seq = self.next_recency;
+        self.next_recency += 1;
+        self.recency.insert(seq, key.clone());
+        self.recency_of.insert(key.clone(), seq);
+    }
+
+    // Makes room for a new key: reclaiming an already-dead entry is always
+    // preferred over evicting something a client could still legitimately
+    // fetch, so the expired scan runs first and the LRU victim is only
+    // taken if nothing is already dead.
+    fn evict_one(&mut self) {
+        let item_lifetime = self.item_lifetime;
+        let expired_key = self.storage
+            .iter()
+            .find(|&(_, value)| !Cache::value_is_alive(value, item_lifetime))
+            .map(|(key, _)| key.clone());
+
+        match expired_key {
+            Some(key) => {
+                if self.remove(&key).is_ok() {
+                    self.stats.record_expired_unfetched();
+                }
+            }
+            None => {
+                let victim = match self.recency.iter().next() {
+                    Some((_, key)) => key.clone(),
+                    None => return,
+                };
+                if self.remove(&victim).is_ok() {
+                    self.stats.record_eviction();
+                }
+            }
         }
     }
 
+    /// Number of live LRU victims evicted so far to make room for new sets.
+    /// Dead entries reclaimed for room are counted as `expired_unfetched`
+    /// instead, not here.
+    pub fn evictions(&self) -> u64 {
+        self.stats.evictions()
+    }
+
+    /// Snapshots the live counters as the `name`/`value` pairs a `stats`
+    /// command response is built from.
+    pub fn stats(&self) -> Vec<Stat> {
+        self.stats.to_stat_vec(self.storage.len() as u64)
+    }
+
 
     pub fn contains_key(&mut self, key: &Key) -> CacheResult<bool> {
         let result = self.get(key);
@@ -85,7 +184,7 @@ impl Cache {
         match result {
             // We know how to interpret found and not found
             Ok(_) => Ok(true),
-            Err(CacheError::KeyNotFound) => Ok(false),
+            Err(Error::KeyNotFound) => Ok(false),
 
             // Some other error
             Err(x) => Err(x),
@@ -94,9 +193,7 @@ impl Cache {
 
     pub fn get(&mut self, key: &Key) -> CacheResult<&Value> {
         // Check key size
-        if !self.check_key_len(key) {
-            return Err(CacheError::KeyTooLong);
-        }
+        self.check_key_len(key)?;
 
         let mut is_alive = false;
         {
@@ -105,12 +202,13 @@ impl Cache {
 
             // We didn't find it
             if opt.is_none() {
-                return Err(CacheError::KeyNotFound);
+                self.stats.record_get_miss();
+                return Err(Error::KeyNotFound);
             }
 
             // From here on we can assume we did find it
             let value: &Value = opt.unwrap();
-            if self.value_is_alive(value) {
+            if Cache::value_is_alive(value, self.item_lifetime) {
                 is_alive = true;
             }
         }
@@ -118,9 +216,16 @@ impl Cache {
         // If the key is dead we evict it and return an error
         if !is_alive {
             self.remove(key).unwrap();
-            return Err(CacheError::KeyNotFound);
+            self.stats.record_get_miss();
+            self.stats.record_expired_unfetched();
+            return Err(Error::KeyNotFound);
         }
 
+        self.stats.record_get_hit();
+
+        // This key is live, so it's now the most recently used
+        self.touch_recency(key);
+
         // Otherwise we retrieve the key again, this time mutable
         let value = self.storage.get_mut(key).unwrap();
 
@@ -136,28 +241,204 @@ impl Cache {
     }
 
     pub fn set(&mut self, key: Key, mut value: Value) -> CacheResult<()> {
-        // Check key & value sizes
-        if !self.check_key_len(&key) {
-            return Err(CacheError::KeyTooLong);
+        // A cache configured with zero capacity can never hold anything;
+        // without this, `evict_one` is a no-op on an empty map and the
+        // insert below would proceed anyway, silently violating capacity.
+        if self.capacity == 0 {
+            return Err(Error::CapacityExceeded);
         }
-        if !self.check_value_len(&value) {
-            return Err(CacheError::ValueTooLong);
+
+        // Check key & value sizes
+        self.check_key_len(&key)?;
+        self.check_value_len(&value)?;
+
+        // Make room if adding a new key would exceed capacity, evicting the
+        // least-recently-used live item (after reclaiming dead ones first)
+        // rather than rejecting the set outright.
+        let replaced_len = self.storage.get(&key).map(|v| v.len() as u64);
+        if replaced_len.is_none() && self.storage.len() as u64 >= self.capacity {
+            self.evict_one();
         }
 
-        // Check capacity if adding new key
-        if !self.storage.contains_key(&key) {
-            if self.storage.len() as u64 == self.capacity {
-                return Err(CacheError::CapacityExceeded);
+        // Update atime and stamp a fresh CAS token for this mutation
+        value.touch();
+        value.cas_id = self.next_cas_id();
+        let value_len = value.len() as u64;
+
+        // Store the value and mark it as the most recently used
+        self.touch_recency(&key);
+        self.storage.insert(key, value);
+        self.stats.record_set(value_len, replaced_len);
+
+        // Return success
+        Ok(())
+    }
+
+    /// Stores `value` under `key` only if the currently stored value's CAS
+    /// token still matches `expected_cas`, giving clients lock-free
+    /// optimistic concurrency. Unlike `set`, this never creates a new key.
+    pub fn cas(&mut self, key: Key, mut value: Value, expected_cas: u64) -> CacheResult<()> {
+        // Check key & value sizes
+        self.check_key_len(&key)?;
+        self.check_value_len(&value)?;
+
+        let item_lifetime = self.item_lifetime;
+        let current_cas = match self.storage.get(&key) {
+            Some(current) if Cache::value_is_alive(current, item_lifetime) => current.cas_id,
+            Some(_) => {
+                self.remove(&key).ok();
+                return Err(Error::KeyNotFound);
             }
+            None => return Err(Error::KeyNotFound),
+        };
+
+        if current_cas != expected_cas {
+            return Err(Error::CasMismatch);
         }
 
-        // Update atime for value
         value.touch();
+        value.cas_id = self.next_cas_id();
+        let value_len = value.len() as u64;
+        let replaced_len = self.storage.get(&key).map_or(0, |v| v.len() as u64);
 
-        // Store the value
+        self.touch_recency(&key);
         self.storage.insert(key, value);
+        self.stats.record_overwrite(value_len, replaced_len);
 
-        // Return success
         Ok(())
     }
+
+    /// Like `set`, but only stores `value` if `key` has no live entry yet.
+    pub fn add(&mut self, key: Key, value: Value) -> CacheResult<()> {
+        self.check_key_len(&key)?;
+
+        let item_lifetime = self.item_lifetime;
+        let exists_alive = self.storage
+            .get(&key)
+            .is_some_and(|v| Cache::value_is_alive(v, item_lifetime));
+        if exists_alive {
+            return Err(Error::NotStored);
+        }
+
+        self.set(key, value)
+    }
+
+    /// Like `set`, but only stores `value` if `key` already has a live
+    /// entry; unlike `set`, this never creates a new key.
+    pub fn replace(&mut self, key: Key, value: Value) -> CacheResult<()> {
+        self.check_key_len(&key)?;
+
+        let item_lifetime = self.item_lifetime;
+        let exists_alive = self.storage
+            .get(&key)
+            .is_some_and(|v| Cache::value_is_alive(v, item_lifetime));
+        if !exists_alive {
+            return Err(Error::NotStored);
+        }
+
+        self.set(key, value)
+    }
+
+    // Shared body for `append`/`prepend`: both concatenate onto the
+    // existing live value's bytes while preserving its flags/exptime, and
+    // fail the same way if there's nothing live to concatenate onto.
+    fn concat(&mut self, key: Key, data: Vec<u8>, prepend: bool) -> CacheResult<()> {
+        self.check_key_len(&key)?;
+
+        let item_lifetime = self.item_lifetime;
+        let (flags, exptime, mut combined) = match self.storage.get(&key) {
+            Some(v) if Cache::value_is_alive(v, item_lifetime) => {
+                (v.flags, v.exptime, v.data().to_vec())
+            }
+            _ => return Err(Error::NotStored),
+        };
+
+        if prepend {
+            let mut new_data = data;
+            new_data.extend_from_slice(&combined);
+            combined = new_data;
+        } else {
+            combined.extend_from_slice(&data);
+        }
+
+        self.set(key, Value::new(flags, exptime, combined))
+    }
+
+    /// Appends `data` to the end of `key`'s existing live value.
+    pub fn append(&mut self, key: Key, data: Vec<u8>) -> CacheResult<()> {
+        self.concat(key, data, false)
+    }
+
+    /// Prepends `data` to the front of `key`'s existing live value.
+    pub fn prepend(&mut self, key: Key, data: Vec<u8>) -> CacheResult<()> {
+        self.concat(key, data, true)
+    }
+
+    /// Removes `key`'s entry, whether or not it was already dead.
+    pub fn delete(&mut self, key: &Key) -> CacheResult<()> {
+        let item_lifetime = self.item_lifetime;
+        let alive = self.storage
+            .get(key)
+            .is_some_and(|v| Cache::value_is_alive(v, item_lifetime));
+
+        if !alive {
+            if self.storage.contains_key(key) {
+                self.remove(key).ok();
+            }
+            self.stats.record_delete_miss();
+            return Err(Error::KeyNotFound);
+        }
+
+        self.remove(key).unwrap();
+        self.stats.record_delete_hit();
+        Ok(())
+    }
+
+    // Shared body for `incr`/`decr`: both parse the stored value as an
+    // unsigned decimal, apply `delta` in the requested direction, and
+    // store the result back as its decimal representation.
+    fn adjust_numeric(&mut self, key: &Key, delta: u64, increment: bool) -> CacheResult<u64> {
+        let item_lifetime = self.item_lifetime;
+        let (flags, exptime, current) = match self.storage.get(key) {
+            Some(v) if Cache::value_is_alive(v, item_lifetime) => {
+                let text = str::from_utf8(v.data()).map_err(|_| Error::NotNumeric)?;
+                let n = text.parse::<u64>().map_err(|_| Error::NotNumeric)?;
+                (v.flags, v.exptime, n)
+            }
+            _ => return Err(Error::KeyNotFound),
+        };
+
+        let new_value = if increment {
+            current.wrapping_add(delta)
+        } else {
+            current.saturating_sub(delta)
+        };
+
+        self.set(key.clone(), Value::new(flags, exptime, new_value.to_string().into_bytes()))?;
+        Ok(new_value)
+    }
+
+    /// Increments `key`'s value, parsed as an unsigned decimal, wrapping at
+    /// `u64::MAX` rather than erroring on overflow.
+    pub fn incr(&mut self, key: &Key, delta: u64) -> CacheResult<u64> {
+        self.adjust_numeric(key, delta, true)
+    }
+
+    /// Decrements `key`'s value, parsed as an unsigned decimal, saturating
+    /// at zero rather than going negative.
+    pub fn decr(&mut self, key: &Key, delta: u64) -> CacheResult<u64> {
+        self.adjust_numeric(key, delta, false)
+    }
+
+    /// Marks every current entry dead as of `delay` seconds from now (0 for
+    /// immediately), without disturbing an entry whose own exptime is
+    /// already sooner than that.
+    pub fn flush_all(&mut self, delay: f64) {
+        let flush_at = time_now_utc() + delay;
+        for value in self.storage.values_mut() {
+            if value.exptime <= 0.0 || value.exptime > flush_at {
+                value.exptime = flush_at;
+            }
+        }
+    }
 }
\ No newline at end of file
@@ -0,0 +1,28 @@
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct Key(Vec<u8>);
+
+impl Key {
+    pub fn new<T: Into<Vec<u8>>>(bytes: T) -> Key {
+        Key(bytes.into())
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl<'a> From<&'a str> for Key {
+    fn from(s: &'a str) -> Key {
+        Key::new(s.as_bytes().to_vec())
+    }
+}
+
+impl From<String> for Key {
+    fn from(s: String) -> Key {
+        Key::new(s.into_bytes())
+    }
+}
@@ -0,0 +1,13 @@
+mod cache;
+mod key;
+mod stats;
+#[cfg(test)]
+mod tests;
+mod typedefs;
+mod utils;
+mod value;
+
+pub use self::cache::Cache;
+pub use self::key::Key;
+pub use self::typedefs::CacheResult;
+pub use self::value::Value;
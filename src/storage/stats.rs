@@ -0,0 +1,110 @@
+use super::utils::time_now_utc;
+
+use protocol::cmd::Stat;
+
+/// Running counters behind the `stats` command, mirroring the subset of
+/// real memcached's stat names this cache can meaningfully report.
+pub struct Stats {
+    start_time: f64,
+    cmd_get: u64,
+    cmd_set: u64,
+    get_hits: u64,
+    get_misses: u64,
+    delete_hits: u64,
+    delete_misses: u64,
+    total_items: u64,
+    bytes: u64,
+    evictions: u64,
+    expired_unfetched: u64,
+}
+
+impl Stats {
+    pub fn new() -> Stats {
+        Stats {
+            start_time: time_now_utc(),
+            cmd_get: 0,
+            cmd_set: 0,
+            get_hits: 0,
+            get_misses: 0,
+            delete_hits: 0,
+            delete_misses: 0,
+            total_items: 0,
+            bytes: 0,
+            evictions: 0,
+            expired_unfetched: 0,
+        }
+    }
+
+    pub fn record_get_hit(&mut self) {
+        self.cmd_get += 1;
+        self.get_hits += 1;
+    }
+
+    pub fn record_get_miss(&mut self) {
+        self.cmd_get += 1;
+        self.get_misses += 1;
+    }
+
+    // `replaced_len` is the length of the value this set overwrote, if any,
+    // so `bytes` tracks what's actually stored rather than only ever growing.
+    pub fn record_set(&mut self, value_len: u64, replaced_len: Option<u64>) {
+        self.cmd_set += 1;
+        self.total_items += 1;
+        self.bytes += value_len;
+        if let Some(old_len) = replaced_len {
+            self.bytes -= old_len;
+        }
+    }
+
+    pub fn record_remove(&mut self, value_len: u64) {
+        self.bytes -= value_len;
+    }
+
+    // Used when a value is overwritten in place (e.g. by `cas`) without
+    // going through `record_set`'s cmd_set/total_items bookkeeping.
+    pub fn record_overwrite(&mut self, new_len: u64, old_len: u64) {
+        self.bytes += new_len;
+        self.bytes -= old_len;
+    }
+
+    pub fn record_delete_hit(&mut self) {
+        self.delete_hits += 1;
+    }
+
+    pub fn record_delete_miss(&mut self) {
+        self.delete_misses += 1;
+    }
+
+    pub fn record_eviction(&mut self) {
+        self.evictions += 1;
+    }
+
+    pub fn record_expired_unfetched(&mut self) {
+        self.expired_unfetched += 1;
+    }
+
+    pub fn evictions(&self) -> u64 {
+        self.evictions
+    }
+
+    /// Renders the live counters as the `name`/`value` pairs a `stats`
+    /// command response is built from.
+    pub fn to_stat_vec(&self, curr_items: u64) -> Vec<Stat> {
+        let now = time_now_utc();
+        vec![
+            Stat::new("uptime", (now - self.start_time).trunc().to_string()),
+            Stat::new("time", now.trunc().to_string()),
+            Stat::new("cmd_get", self.cmd_get.to_string()),
+            Stat::new("cmd_set", self.cmd_set.to_string()),
+            Stat::new("get_hits", self.get_hits.to_string()),
+            Stat::new("get_misses", self.get_misses.to_string()),
+            Stat::new("delete_hits", self.delete_hits.to_string()),
+            Stat::new("delete_misses", self.delete_misses.to_string()),
+            Stat::new("curr_items", curr_items.to_string()),
+            Stat::new("total_items", self.total_items.to_string()),
+            Stat::new("bytes", self.bytes.to_string()),
+            Stat::new("evictions", self.evictions.to_string()),
+            Stat::new("expired_unfetched", self.expired_unfetched.to_string()),
+        ]
+    }
+}
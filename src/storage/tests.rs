@@ -0,0 +1,274 @@
+use protocol::cmd::Stat;
+
+use error::Error;
+use super::Cache;
+use super::Key;
+use super::Value;
+
+fn key(s: &str) -> Key {
+    Key::from(s)
+}
+
+fn stat_value(stats: &[Stat], name: &str) -> String {
+    stats.iter().find(|s| s.name == name).unwrap().value.clone()
+}
+
+#[test]
+fn test_set_then_get_roundtrip() {
+    let mut cache = Cache::new(10);
+    cache.set(key("a"), Value::new(0, 0.0, vec![1, 2, 3])).unwrap();
+
+    let value = cache.get(&key("a")).unwrap();
+    assert_eq!(value.data(), &[1, 2, 3]);
+}
+
+#[test]
+fn test_get_missing_key_errors() {
+    let mut cache = Cache::new(10);
+    let err = cache.get(&key("missing")).unwrap_err();
+    assert_eq!(err, Error::KeyNotFound);
+}
+
+#[test]
+fn test_set_evicts_least_recently_used_when_full() {
+    let mut cache = Cache::new(2);
+    cache.set(key("a"), Value::new(0, 0.0, vec![1])).unwrap();
+    cache.set(key("b"), Value::new(0, 0.0, vec![2])).unwrap();
+
+    // Touch "a" so "b" becomes the least recently used entry.
+    cache.get(&key("a")).unwrap();
+
+    cache.set(key("c"), Value::new(0, 0.0, vec![3])).unwrap();
+
+    assert_eq!(cache.get(&key("a")).unwrap().data(), &[1]);
+    assert_eq!(cache.get(&key("c")).unwrap().data(), &[3]);
+    assert_eq!(cache.get(&key("b")).unwrap_err(), Error::KeyNotFound);
+    assert_eq!(cache.evictions(), 1);
+}
+
+#[test]
+fn test_set_reclaims_expired_entry_before_evicting_live_lru() {
+    let mut cache = Cache::new(2);
+    // An exptime of 1.0 is one second past the epoch, so this entry is
+    // already dead by the time it's read back.
+    cache.set(key("dead"), Value::new(0, 1.0, vec![1])).unwrap();
+    cache.set(key("alive"), Value::new(0, 0.0, vec![2])).unwrap();
+
+    cache.set(key("new"), Value::new(0, 0.0, vec![3])).unwrap();
+
+    assert_eq!(cache.get(&key("dead")).unwrap_err(), Error::KeyNotFound);
+    assert_eq!(cache.get(&key("alive")).unwrap().data(), &[2]);
+    assert_eq!(cache.get(&key("new")).unwrap().data(), &[3]);
+    assert_eq!(cache.evictions(), 0);
+}
+
+#[test]
+fn test_cas_succeeds_when_token_matches() {
+    let mut cache = Cache::new(10);
+    cache.set(key("a"), Value::new(0, 0.0, vec![1])).unwrap();
+    let cas_id = cache.get(&key("a")).unwrap().cas_id;
+
+    cache.cas(key("a"), Value::new(0, 0.0, vec![2]), cas_id).unwrap();
+
+    assert_eq!(cache.get(&key("a")).unwrap().data(), &[2]);
+}
+
+#[test]
+fn test_cas_fails_when_token_is_stale() {
+    let mut cache = Cache::new(10);
+    cache.set(key("a"), Value::new(0, 0.0, vec![1])).unwrap();
+    let stale_cas_id = cache.get(&key("a")).unwrap().cas_id;
+
+    // A second set bumps the CAS token, so the one we captured is stale.
+    cache.set(key("a"), Value::new(0, 0.0, vec![2])).unwrap();
+
+    let err = cache.cas(key("a"), Value::new(0, 0.0, vec![3]), stale_cas_id).unwrap_err();
+    assert_eq!(err, Error::CasMismatch);
+    assert_eq!(cache.get(&key("a")).unwrap().data(), &[2]);
+}
+
+#[test]
+fn test_cas_fails_when_key_is_missing() {
+    let mut cache = Cache::new(10);
+    let err = cache.cas(key("missing"), Value::new(0, 0.0, vec![1]), 0).unwrap_err();
+    assert_eq!(err, Error::KeyNotFound);
+}
+
+#[test]
+fn test_stats_track_gets_sets_and_bytes() {
+    let mut cache = Cache::new(10);
+    cache.set(key("a"), Value::new(0, 0.0, vec![1, 2, 3])).unwrap();
+    cache.get(&key("a")).unwrap();
+    cache.get(&key("missing")).unwrap_err();
+
+    let stats = cache.stats();
+    assert_eq!(stat_value(&stats, "cmd_set"), "1");
+    assert_eq!(stat_value(&stats, "cmd_get"), "2");
+    assert_eq!(stat_value(&stats, "get_hits"), "1");
+    assert_eq!(stat_value(&stats, "get_misses"), "1");
+    assert_eq!(stat_value(&stats, "curr_items"), "1");
+    assert_eq!(stat_value(&stats, "total_items"), "1");
+    assert_eq!(stat_value(&stats, "bytes"), "3");
+}
+
+#[test]
+fn test_stats_track_evictions_and_shrink_bytes_on_overwrite() {
+    let mut cache = Cache::new(1);
+    cache.set(key("a"), Value::new(0, 0.0, vec![1, 2, 3])).unwrap();
+    cache.set(key("a"), Value::new(0, 0.0, vec![9])).unwrap();
+
+    let stats = cache.stats();
+    assert_eq!(stat_value(&stats, "bytes"), "1");
+
+    cache.set(key("b"), Value::new(0, 0.0, vec![4])).unwrap();
+    let stats = cache.stats();
+    assert_eq!(stat_value(&stats, "evictions"), "1");
+}
+
+#[test]
+fn test_add_fails_when_key_already_has_a_live_value() {
+    let mut cache = Cache::new(10);
+    cache.set(key("a"), Value::new(0, 0.0, vec![1])).unwrap();
+
+    let err = cache.add(key("a"), Value::new(0, 0.0, vec![2])).unwrap_err();
+    assert_eq!(err, Error::NotStored);
+    assert_eq!(cache.get(&key("a")).unwrap().data(), &[1]);
+}
+
+#[test]
+fn test_add_succeeds_when_key_is_absent() {
+    let mut cache = Cache::new(10);
+    cache.add(key("a"), Value::new(0, 0.0, vec![1])).unwrap();
+    assert_eq!(cache.get(&key("a")).unwrap().data(), &[1]);
+}
+
+#[test]
+fn test_replace_fails_when_key_is_absent() {
+    let mut cache = Cache::new(10);
+    let err = cache.replace(key("missing"), Value::new(0, 0.0, vec![1])).unwrap_err();
+    assert_eq!(err, Error::NotStored);
+}
+
+#[test]
+fn test_replace_succeeds_when_key_has_a_live_value() {
+    let mut cache = Cache::new(10);
+    cache.set(key("a"), Value::new(0, 0.0, vec![1])).unwrap();
+    cache.replace(key("a"), Value::new(0, 0.0, vec![2])).unwrap();
+    assert_eq!(cache.get(&key("a")).unwrap().data(), &[2]);
+}
+
+#[test]
+fn test_append_concatenates_onto_existing_value() {
+    let mut cache = Cache::new(10);
+    cache.set(key("a"), Value::new(7, 0.0, vec![1, 2])).unwrap();
+    cache.append(key("a"), vec![3, 4]).unwrap();
+
+    let value = cache.get(&key("a")).unwrap();
+    assert_eq!(value.data(), &[1, 2, 3, 4]);
+    assert_eq!(value.flags, 7);
+}
+
+#[test]
+fn test_prepend_concatenates_before_existing_value() {
+    let mut cache = Cache::new(10);
+    cache.set(key("a"), Value::new(0, 0.0, vec![3, 4])).unwrap();
+    cache.prepend(key("a"), vec![1, 2]).unwrap();
+
+    assert_eq!(cache.get(&key("a")).unwrap().data(), &[1, 2, 3, 4]);
+}
+
+#[test]
+fn test_append_fails_when_key_is_missing() {
+    let mut cache = Cache::new(10);
+    let err = cache.append(key("missing"), vec![1]).unwrap_err();
+    assert_eq!(err, Error::NotStored);
+}
+
+#[test]
+fn test_delete_removes_a_live_key() {
+    let mut cache = Cache::new(10);
+    cache.set(key("a"), Value::new(0, 0.0, vec![1])).unwrap();
+    cache.delete(&key("a")).unwrap();
+    assert_eq!(cache.get(&key("a")).unwrap_err(), Error::KeyNotFound);
+}
+
+#[test]
+fn test_delete_fails_when_key_is_missing() {
+    let mut cache = Cache::new(10);
+    let err = cache.delete(&key("missing")).unwrap_err();
+    assert_eq!(err, Error::KeyNotFound);
+}
+
+#[test]
+fn test_incr_adds_delta_to_numeric_value() {
+    let mut cache = Cache::new(10);
+    cache.set(key("n"), Value::new(0, 0.0, b"10".to_vec())).unwrap();
+
+    let new_value = cache.incr(&key("n"), 5).unwrap();
+    assert_eq!(new_value, 15);
+    assert_eq!(cache.get(&key("n")).unwrap().data(), b"15");
+}
+
+#[test]
+fn test_decr_saturates_at_zero() {
+    let mut cache = Cache::new(10);
+    cache.set(key("n"), Value::new(0, 0.0, b"3".to_vec())).unwrap();
+
+    let new_value = cache.decr(&key("n"), 10).unwrap();
+    assert_eq!(new_value, 0);
+}
+
+#[test]
+fn test_incr_fails_on_non_numeric_value() {
+    let mut cache = Cache::new(10);
+    cache.set(key("s"), Value::new(0, 0.0, b"not-a-number".to_vec())).unwrap();
+
+    let err = cache.incr(&key("s"), 1).unwrap_err();
+    assert_eq!(err, Error::NotNumeric);
+}
+
+#[test]
+fn test_incr_fails_when_key_is_missing() {
+    let mut cache = Cache::new(10);
+    let err = cache.incr(&key("missing"), 1).unwrap_err();
+    assert_eq!(err, Error::KeyNotFound);
+}
+
+#[test]
+fn test_set_fails_when_key_exceeds_maxlen() {
+    let mut cache = Cache::new(10);
+    cache.with_key_maxlen(3);
+
+    let err = cache.set(key("toolong"), Value::new(0, 0.0, vec![1])).unwrap_err();
+    assert_eq!(err, Error::KeyTooLong { limit: 3, got: 7 });
+}
+
+#[test]
+fn test_set_fails_when_value_exceeds_maxlen() {
+    let mut cache = Cache::new(10);
+    cache.with_value_maxlen(2);
+
+    let err = cache.set(key("a"), Value::new(0, 0.0, vec![1, 2, 3])).unwrap_err();
+    assert_eq!(err, Error::ValueTooLong { limit: 2, got: 3 });
+}
+
+#[test]
+fn test_set_fails_when_capacity_is_zero() {
+    let mut cache = Cache::new(0);
+
+    let err = cache.set(key("a"), Value::new(0, 0.0, vec![1])).unwrap_err();
+    assert_eq!(err, Error::CapacityExceeded);
+    assert_eq!(cache.get(&key("a")).unwrap_err(), Error::KeyNotFound);
+}
+
+#[test]
+fn test_flush_all_marks_every_entry_dead_immediately() {
+    let mut cache = Cache::new(10);
+    cache.set(key("a"), Value::new(0, 0.0, vec![1])).unwrap();
+    cache.set(key("b"), Value::new(0, 0.0, vec![2])).unwrap();
+
+    cache.flush_all(0.0);
+
+    assert_eq!(cache.get(&key("a")).unwrap_err(), Error::KeyNotFound);
+    assert_eq!(cache.get(&key("b")).unwrap_err(), Error::KeyNotFound);
+}
@@ -0,0 +1,3 @@
+use error::Error;
+
+pub type CacheResult<T> = Result<T, Error>;
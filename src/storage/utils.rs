@@ -0,0 +1,9 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub fn time_now_utc() -> f64 {
+    let since_epoch = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("system clock is set before the unix epoch");
+
+    since_epoch.as_secs() as f64 + since_epoch.subsec_nanos() as f64 / 1_000_000_000.0
+}
@@ -0,0 +1,34 @@
+use super::utils::time_now_utc;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Value {
+    pub flags: u32,
+    pub exptime: f64, // absolute unix time the value dies at, <= 0 for "no per-item expiry"
+    pub atime: f64, // unix time of the last access, used for item_lifetime expiry
+    pub cas_id: u64, // bumped by Cache on every mutating set, compared by Cache::cas
+    data: Vec<u8>,
+}
+
+impl Value {
+    pub fn new(flags: u32, exptime: f64, data: Vec<u8>) -> Value {
+        Value {
+            flags: flags,
+            exptime: exptime,
+            atime: time_now_utc(),
+            cas_id: 0,
+            data: data,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn touch(&mut self) {
+        self.atime = time_now_utc();
+    }
+}
@@ -0,0 +1,388 @@
+#[cfg(test)]
+mod tests;
+
+use std::io::{self, Read, Write};
+use std::str::FromStr;
+
+use protocol::cmd::Cas;
+use protocol::cmd::Cmd;
+use protocol::cmd::Delete;
+use protocol::cmd::FlushAll;
+use protocol::cmd::Get;
+use protocol::cmd::IncrDecr;
+use protocol::cmd::Resp;
+use protocol::cmd::Set;
+
+use error::Error;
+use error::Verb;
+
+// Mirrors tcp_transport::LINE_MAXLEN: the longest line we'll buffer while
+// looking for a command terminator before giving up.
+const LINE_MAXLEN: usize = 1024;
+
+// Mirrors tcp_transport::DATA_MAXLEN: the longest declared payload we'll
+// trust from a `set`-shaped command or `cas` before computing how many
+// bytes to wait for. Without this, a crafted declared length overflows
+// `line_len + data_len + 2` (or slices out of bounds) before the cache
+// ever gets a chance to enforce its own value_maxlen.
+const DATA_MAXLEN: usize = 1024 * 1024;
+
+/// Outcome of a single non-blocking attempt to read a command: either a
+/// full command was parsed out of what's been buffered so far, or the
+/// stream hasn't handed over enough bytes yet and the caller should poll
+/// again once more data is ready, instead of treating it as an error.
+#[derive(Debug, PartialEq, Clone)]
+pub enum PollCmd {
+    Ready(Cmd),
+    WouldBlock,
+}
+
+/// The blocking transport interface `TcpTransport` already implements:
+/// one full command in, one full response out, synchronously, one thread
+/// per connection.
+pub trait SyncTransport {
+    fn read_cmd(&mut self) -> Result<Cmd, Error>;
+    fn write_resp(&mut self, resp: &Resp) -> Result<(), Error>;
+    fn flush_writes(&mut self) -> Result<(), Error>;
+}
+
+/// A readiness-driven counterpart to `SyncTransport`: reads never block
+/// waiting for a full command to arrive, and writes can be drained
+/// incrementally, so a single thread can service many connections behind
+/// an event loop instead of one thread per socket.
+pub trait AsyncTransport {
+    fn poll_read_cmd(&mut self) -> Result<PollCmd, Error>;
+    fn queue_resp(&mut self, resp: &Resp) -> Result<(), Error>;
+    fn poll_write(&mut self) -> Result<usize, Error>;
+    fn has_pending_writes(&self) -> bool;
+}
+
+pub struct AsyncTcpTransport<S: Read + Write> {
+    stream: S,
+    read_buffer: Vec<u8>,
+    write_buffer: Vec<u8>,
+}
+
+impl<S: Read + Write> AsyncTcpTransport<S> {
+    pub fn new(stream: S) -> AsyncTcpTransport<S> {
+        AsyncTcpTransport {
+            stream: stream,
+            read_buffer: Vec::new(),
+            write_buffer: Vec::new(),
+        }
+    }
+
+    pub fn get_stream(&self) -> &S {
+        &self.stream
+    }
+
+    // Pulls whatever the stream currently has to offer into `read_buffer`
+    // without waiting for more. A single `read` per poll mirrors one
+    // readiness notification off an event loop; a `WouldBlock` (or a
+    // zero-byte read, for streams that don't distinguish the two) just
+    // means "nothing new this tick", not a failure.
+    fn fill_read_buffer(&mut self) -> Result<(), Error> {
+        let mut chunk = [0u8; 4096];
+        match self.stream.read(&mut chunk) {
+            Ok(0) => Ok(()),
+            Ok(n) => {
+                self.read_buffer.extend_from_slice(&chunk[..n]);
+                Ok(())
+            }
+            Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => Ok(()),
+            // A genuine read failure means the connection is gone; there's
+            // no specific byte count we were waiting on at this layer, so
+            // report it as an eof we weren't expecting yet.
+            Err(_) => Err(Error::UnexpectedEof { expected: 0, got: 0 }),
+        }
+    }
+}
+
+impl<S: Read + Write> AsyncTransport for AsyncTcpTransport<S> {
+    fn poll_read_cmd(&mut self) -> Result<PollCmd, Error> {
+        self.fill_read_buffer()?;
+
+        match try_parse_cmd(&self.read_buffer)? {
+            Some((cmd, consumed)) => {
+                self.read_buffer.drain(..consumed);
+                Ok(PollCmd::Ready(cmd))
+            }
+            None => Ok(PollCmd::WouldBlock),
+        }
+    }
+
+    fn queue_resp(&mut self, resp: &Resp) -> Result<(), Error> {
+        encode_resp(resp, &mut self.write_buffer);
+        Ok(())
+    }
+
+    fn poll_write(&mut self) -> Result<usize, Error> {
+        if self.write_buffer.is_empty() {
+            return Ok(0);
+        }
+
+        match self.stream.write(&self.write_buffer) {
+            Ok(n) => {
+                self.write_buffer.drain(..n);
+                Ok(n)
+            }
+            Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => Ok(0),
+            Err(_) => Err(Error::StreamWrite),
+        }
+    }
+
+    fn has_pending_writes(&self) -> bool {
+        !self.write_buffer.is_empty()
+    }
+}
+
+
+// Parsing: identical wire format to the sync text protocol, but operating
+// over whatever's been buffered so far rather than a blocking stream, so
+// it can report "not enough yet" instead of erroring on a short read.
+
+fn find_crlf(buf: &[u8]) -> Option<usize> {
+    buf.windows(2).position(|w| w == [13, 10])
+}
+
+fn split_word(buf: &[u8]) -> (&[u8], &[u8]) {
+    match buf.iter().position(|&b| b == 32) {
+        Some(i) => (&buf[..i], &buf[i..]),
+        None => (buf, &buf[0..0]),
+    }
+}
+
+fn next_word(rest: &[u8]) -> Result<(&[u8], &[u8]), Error> {
+    if rest.first() != Some(&32) {
+        return Err(Error::CommandParse);
+    }
+    Ok(split_word(&rest[1..]))
+}
+
+fn as_string(bytes: &[u8]) -> Result<String, Error> {
+    String::from_utf8(bytes.to_vec()).map_err(|_| Error::Utf8)
+}
+
+fn as_number<T: FromStr>(bytes: &[u8]) -> Result<T, Error> {
+    let s = as_string(bytes)?;
+    s.parse::<T>().map_err(|_| Error::NumberParse)
+}
+
+fn try_parse_cmd(buf: &[u8]) -> Result<Option<(Cmd, usize)>, Error> {
+    let line_end = match find_crlf(buf) {
+        Some(i) => i,
+        None => {
+            if buf.len() > LINE_MAXLEN {
+                return Err(Error::LineTooLong { limit: LINE_MAXLEN });
+            }
+            return Ok(None);
+        }
+    };
+
+    let line = &buf[..line_end];
+    let line_len = line_end + 2;
+    let (verb, rest) = split_word(line);
+
+    match verb {
+        b"get" | b"gets" => {
+            let (key_bytes, rest) = next_word(rest)?;
+            if !rest.is_empty() {
+                return Err(Error::CommandParse);
+            }
+            let key = as_string(key_bytes)?;
+            let get = if verb == b"gets" { Get::gets(key) } else { Get::new(key) };
+            Ok(Some((Cmd::Get(get), line_len)))
+        }
+        b"set" => parse_set_or_cas(rest, line_len, buf, false),
+        b"cas" => parse_set_or_cas(rest, line_len, buf, true),
+        b"add" => parse_set_shaped(rest, line_len, buf, Cmd::Add),
+        b"replace" => parse_set_shaped(rest, line_len, buf, Cmd::Replace),
+        b"append" => parse_set_shaped(rest, line_len, buf, Cmd::Append),
+        b"prepend" => parse_set_shaped(rest, line_len, buf, Cmd::Prepend),
+        b"delete" => {
+            let (key_bytes, rest) = next_word(rest)?;
+            if !rest.is_empty() {
+                return Err(Error::CommandParse);
+            }
+            let key = as_string(key_bytes)?;
+            Ok(Some((Cmd::Delete(Delete::new(key)), line_len)))
+        }
+        b"incr" | b"decr" => {
+            let (key_bytes, rest) = next_word(rest)?;
+            let (delta_bytes, rest) = next_word(rest)?;
+            if !rest.is_empty() {
+                return Err(Error::CommandParse);
+            }
+            let key = as_string(key_bytes)?;
+            let delta = as_number::<u64>(delta_bytes)?;
+            let incr_decr = IncrDecr::new(key, delta);
+            let cmd = if verb == b"incr" { Cmd::Incr(incr_decr) } else { Cmd::Decr(incr_decr) };
+            Ok(Some((cmd, line_len)))
+        }
+        b"flush_all" => {
+            let mut flush = FlushAll::new();
+            if !rest.is_empty() {
+                let (delay_bytes, rest) = next_word(rest)?;
+                if !rest.is_empty() {
+                    return Err(Error::CommandParse);
+                }
+                flush.with_delay(as_number::<f64>(delay_bytes)?);
+            }
+            Ok(Some((Cmd::FlushAll(flush), line_len)))
+        }
+        b"stats" => {
+            if !rest.is_empty() {
+                return Err(Error::CommandParse);
+            }
+            Ok(Some((Cmd::Stats, line_len)))
+        }
+        _ => Err(Error::InvalidCommand { verb: Verb::new(verb) }),
+    }
+}
+
+fn parse_set_or_cas(
+    rest: &[u8],
+    line_len: usize,
+    buf: &[u8],
+    with_cas: bool,
+) -> Result<Option<(Cmd, usize)>, Error> {
+    let (key_bytes, rest) = next_word(rest)?;
+    let (flags_bytes, rest) = next_word(rest)?;
+    let (exptime_bytes, rest) = next_word(rest)?;
+    let (len_bytes, rest) = next_word(rest)?;
+
+    let (cas_bytes, rest) = if with_cas {
+        let (cas_bytes, rest) = next_word(rest)?;
+        (Some(cas_bytes), rest)
+    } else {
+        (None, rest)
+    };
+
+    if !rest.is_empty() {
+        return Err(Error::CommandParse);
+    }
+
+    let key = as_string(key_bytes)?;
+    let flags = as_number::<u32>(flags_bytes)?;
+    let exptime = as_number::<f64>(exptime_bytes)?;
+    let data_len = as_number::<usize>(len_bytes)?;
+    let cas_unique = match cas_bytes {
+        Some(bytes) => Some(as_number::<u64>(bytes)?),
+        None => None,
+    };
+
+    if data_len > DATA_MAXLEN {
+        return Err(Error::ValueTooLong { limit: DATA_MAXLEN as u64, got: data_len as u64 });
+    }
+
+    let needed = line_len + data_len + 2;
+    if buf.len() < needed {
+        return Ok(None);
+    }
+
+    let payload = &buf[line_len..line_len + data_len];
+    let terminator = &buf[line_len + data_len..needed];
+    if terminator != [13, 10] {
+        return Err(Error::BadLineTerminator);
+    }
+
+    let cmd = match cas_unique {
+        Some(cas) => {
+            let mut req = Cas::new(key, flags, payload.to_vec(), cas);
+            req.with_exptime(exptime);
+            Cmd::Cas(req)
+        }
+        None => {
+            let mut set = Set::new(key, flags, payload.to_vec());
+            set.with_exptime(exptime);
+            Cmd::Set(set)
+        }
+    };
+
+    Ok(Some((cmd, needed)))
+}
+
+// Shared body parser for the `set`-shaped commands that don't carry a CAS
+// token (`add`/`replace`/`append`/`prepend`): identical wire format to
+// `set`, just tagged with a different `Cmd` variant.
+fn parse_set_shaped<F: FnOnce(Set) -> Cmd>(
+    rest: &[u8],
+    line_len: usize,
+    buf: &[u8],
+    wrap: F,
+) -> Result<Option<(Cmd, usize)>, Error> {
+    let (key_bytes, rest) = next_word(rest)?;
+    let (flags_bytes, rest) = next_word(rest)?;
+    let (exptime_bytes, rest) = next_word(rest)?;
+    let (len_bytes, rest) = next_word(rest)?;
+    if !rest.is_empty() {
+        return Err(Error::CommandParse);
+    }
+
+    let key = as_string(key_bytes)?;
+    let flags = as_number::<u32>(flags_bytes)?;
+    let exptime = as_number::<f64>(exptime_bytes)?;
+    let data_len = as_number::<usize>(len_bytes)?;
+
+    if data_len > DATA_MAXLEN {
+        return Err(Error::ValueTooLong { limit: DATA_MAXLEN as u64, got: data_len as u64 });
+    }
+
+    let needed = line_len + data_len + 2;
+    if buf.len() < needed {
+        return Ok(None);
+    }
+
+    let payload = &buf[line_len..line_len + data_len];
+    let terminator = &buf[line_len + data_len..needed];
+    if terminator != [13, 10] {
+        return Err(Error::BadLineTerminator);
+    }
+
+    let mut set = Set::new(key, flags, payload.to_vec());
+    set.with_exptime(exptime);
+    Ok(Some((wrap(set), needed)))
+}
+
+fn encode_resp(resp: &Resp, out: &mut Vec<u8>) {
+    match *resp {
+        Resp::Error => out.extend_from_slice(b"ERROR\r\n"),
+        Resp::Stored => out.extend_from_slice(b"STORED\r\n"),
+        Resp::Exists => out.extend_from_slice(b"EXISTS\r\n"),
+        Resp::NotFound => out.extend_from_slice(b"NOT_FOUND\r\n"),
+        Resp::NotStored => out.extend_from_slice(b"NOT_STORED\r\n"),
+        Resp::Deleted => out.extend_from_slice(b"DELETED\r\n"),
+        Resp::Number(n) => out.extend_from_slice(format!("{}\r\n", n).as_bytes()),
+        Resp::ClientError(ref message) => {
+            out.extend_from_slice(format!("CLIENT_ERROR {}\r\n", message).as_bytes())
+        }
+        Resp::NotNumeric => {
+            out.extend_from_slice(b"CLIENT_ERROR cannot increment or decrement non-numeric value\r\n")
+        }
+        Resp::ServerError(ref message) => {
+            out.extend_from_slice(format!("SERVER_ERROR {}\r\n", message).as_bytes())
+        }
+        Resp::Ok => out.extend_from_slice(b"OK\r\n"),
+        Resp::Value(ref value) => {
+            let header = match value.cas_unique {
+                Some(cas) => format!(
+                    "VALUE {} {} {} {}\r\n",
+                    value.key,
+                    value.flags,
+                    value.data.len(),
+                    cas
+                ),
+                None => format!("VALUE {} {} {}\r\n", value.key, value.flags, value.data.len()),
+            };
+            out.extend_from_slice(header.as_bytes());
+            out.extend_from_slice(&value.data);
+            out.extend_from_slice(b"\r\n");
+        }
+        Resp::Stats(ref stats) => {
+            for stat in stats {
+                out.extend_from_slice(format!("{} {}\r\n", stat.name, stat.value).as_bytes());
+            }
+            out.extend_from_slice(b"END\r\n");
+        }
+    }
+}
@@ -0,0 +1,162 @@
+use super::super::test_stream::TestStream;
+use super::AsyncTcpTransport;
+use super::AsyncTransport;
+use super::PollCmd;
+use super::DATA_MAXLEN;
+
+use error::Error;
+
+use protocol::cmd::Cmd;
+use protocol::cmd::Delete;
+use protocol::cmd::FlushAll;
+use protocol::cmd::Get;
+use protocol::cmd::IncrDecr;
+use protocol::cmd::Resp;
+use protocol::cmd::Set;
+use protocol::cmd::Stat;
+
+
+#[test]
+fn test_poll_read_cmd_ready_in_one_shot() {
+    let ts = TestStream::new("get x\r\n".to_string().into_bytes());
+    let mut transport = AsyncTcpTransport::new(ts);
+
+    let cmd = transport.poll_read_cmd().unwrap();
+    assert_eq!(cmd, PollCmd::Ready(Cmd::Get(Get::new("x"))));
+}
+
+#[test]
+fn test_poll_read_cmd_set_declared_length_overflow_rejected() {
+    // A declared length near usize::MAX must not reach the
+    // `line_len + data_len + 2` arithmetic unchecked, and must not panic
+    // slicing the buffer for the payload/terminator.
+    let cmd_str = "set x 0 0 18446744073709551615\r\nA\r\n".to_string();
+    let ts = TestStream::new(cmd_str.into_bytes());
+    let mut transport = AsyncTcpTransport::new(ts);
+
+    let err = transport.poll_read_cmd().unwrap_err();
+    assert_eq!(err, Error::ValueTooLong { limit: DATA_MAXLEN as u64, got: 18446744073709551615 });
+}
+
+#[test]
+fn test_poll_read_cmd_would_block_until_full_command_buffered() {
+    // Delivered one byte at a time, so every poll but the last should
+    // report WouldBlock rather than erroring on the short read.
+    let cmd_str = "get x\r\n".to_string();
+    let ts = TestStream::new_chunked(cmd_str.into_bytes(), 1);
+    let mut transport = AsyncTcpTransport::new(ts);
+
+    let mut ready_count = 0;
+    let mut would_block_count = 0;
+
+    for _ in 0..7 {
+        match transport.poll_read_cmd().unwrap() {
+            PollCmd::Ready(cmd) => {
+                ready_count += 1;
+                assert_eq!(cmd, Cmd::Get(Get::new("x")));
+            }
+            PollCmd::WouldBlock => would_block_count += 1,
+        }
+    }
+
+    assert_eq!(ready_count, 1);
+    assert_eq!(would_block_count, 6);
+}
+
+#[test]
+fn test_poll_read_cmd_handles_partial_set_payload_across_polls() {
+    let cmd_str = "set x 0 0 3\r\nabc\r\n".to_string();
+    let ts = TestStream::new_chunked(cmd_str.into_bytes(), 4);
+    let mut transport = AsyncTcpTransport::new(ts);
+
+    let cmd = loop {
+        match transport.poll_read_cmd().unwrap() {
+            PollCmd::Ready(cmd) => break cmd,
+            PollCmd::WouldBlock => continue,
+        }
+    };
+
+    assert_eq!(cmd, Cmd::Set(Set::new("x", 0, vec![97, 98, 99])));
+}
+
+#[test]
+fn test_poll_read_cmd_then_reads_next_command() {
+    let cmd_str = "get x\r\nget y\r\n".to_string();
+    let ts = TestStream::new(cmd_str.into_bytes());
+    let mut transport = AsyncTcpTransport::new(ts);
+
+    assert_eq!(
+        transport.poll_read_cmd().unwrap(),
+        PollCmd::Ready(Cmd::Get(Get::new("x")))
+    );
+    assert_eq!(
+        transport.poll_read_cmd().unwrap(),
+        PollCmd::Ready(Cmd::Get(Get::new("y")))
+    );
+}
+
+#[test]
+fn test_queue_and_poll_write_drains_incrementally() {
+    let ts = TestStream::new(vec![]);
+    let mut transport = AsyncTcpTransport::new(ts);
+
+    transport.queue_resp(&Resp::Stored).unwrap();
+    assert!(transport.has_pending_writes());
+
+    let mut written = 0;
+    while transport.has_pending_writes() {
+        written += transport.poll_write().unwrap();
+    }
+
+    assert_eq!(written, "STORED\r\n".len());
+    assert_eq!(transport.get_stream().outgoing, b"STORED\r\n");
+}
+
+#[test]
+fn test_poll_read_cmd_add_ok() {
+    let ts = TestStream::new("add x 0 0 3\r\nabc\r\n".to_string().into_bytes());
+    let mut transport = AsyncTcpTransport::new(ts);
+
+    let cmd = transport.poll_read_cmd().unwrap();
+    assert_eq!(cmd, PollCmd::Ready(Cmd::Add(Set::new("x", 0, vec![97, 98, 99]))));
+}
+
+#[test]
+fn test_poll_read_cmd_delete_ok() {
+    let ts = TestStream::new("delete x\r\n".to_string().into_bytes());
+    let mut transport = AsyncTcpTransport::new(ts);
+
+    let cmd = transport.poll_read_cmd().unwrap();
+    assert_eq!(cmd, PollCmd::Ready(Cmd::Delete(Delete::new("x"))));
+}
+
+#[test]
+fn test_poll_read_cmd_incr_ok() {
+    let ts = TestStream::new("incr x 5\r\n".to_string().into_bytes());
+    let mut transport = AsyncTcpTransport::new(ts);
+
+    let cmd = transport.poll_read_cmd().unwrap();
+    assert_eq!(cmd, PollCmd::Ready(Cmd::Incr(IncrDecr::new("x", 5))));
+}
+
+#[test]
+fn test_poll_read_cmd_flush_all_ok() {
+    let ts = TestStream::new("flush_all\r\n".to_string().into_bytes());
+    let mut transport = AsyncTcpTransport::new(ts);
+
+    let cmd = transport.poll_read_cmd().unwrap();
+    assert_eq!(cmd, PollCmd::Ready(Cmd::FlushAll(FlushAll::new())));
+}
+
+#[test]
+fn test_queue_resp_stats() {
+    let ts = TestStream::new(vec![]);
+    let mut transport = AsyncTcpTransport::new(ts);
+
+    transport
+        .queue_resp(&Resp::Stats(vec![Stat::new("curr_items", "0".to_string())]))
+        .unwrap();
+    transport.poll_write().unwrap();
+
+    assert_eq!(transport.get_stream().outgoing, b"curr_items 0\r\nEND\r\n");
+}
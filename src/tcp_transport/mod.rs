@@ -0,0 +1,687 @@
+pub mod async_transport;
+#[cfg(test)]
+pub mod test_stream;
+#[cfg(test)]
+mod tests;
+
+pub use self::async_transport::AsyncTcpTransport;
+pub use self::async_transport::AsyncTransport;
+pub use self::async_transport::PollCmd;
+pub use self::async_transport::SyncTransport;
+
+use std::io::{Read, Write};
+use std::str::FromStr;
+
+use error::Error;
+use error::Verb;
+use protocol::cmd::Cas;
+use protocol::cmd::Cmd;
+use protocol::cmd::Delete;
+use protocol::cmd::FlushAll;
+use protocol::cmd::Get;
+use protocol::cmd::IncrDecr;
+use protocol::cmd::Resp;
+use protocol::cmd::Set;
+
+// Longest line we'll buffer while looking for a text-protocol command
+// terminator before giving up.
+const LINE_MAXLEN: usize = 1024;
+
+// Longest declared payload we'll trust from a `set`-shaped command or `cas`
+// before reading it off the wire. Without this, a crafted declared length
+// (e.g. a value close to usize::MAX) would overflow the `data_len + 2` read
+// size or panic the subsequent split_off before the cache ever gets a
+// chance to enforce its own value_maxlen.
+pub(crate) const DATA_MAXLEN: usize = 1024 * 1024;
+
+// memcached binary protocol magic bytes (protocol_binary.h upstream).
+const BINARY_REQUEST_MAGIC: u8 = 0x80;
+const BINARY_RESPONSE_MAGIC: u8 = 0x81;
+
+const OPCODE_GET: u8 = 0x00;
+const OPCODE_SET: u8 = 0x01;
+const OPCODE_ADD: u8 = 0x02;
+const OPCODE_REPLACE: u8 = 0x03;
+const OPCODE_DELETE: u8 = 0x04;
+const OPCODE_INCREMENT: u8 = 0x05;
+const OPCODE_DECREMENT: u8 = 0x06;
+const OPCODE_FLUSH: u8 = 0x08;
+const OPCODE_APPEND: u8 = 0x0e;
+const OPCODE_PREPEND: u8 = 0x0f;
+const OPCODE_STAT: u8 = 0x0b;
+
+const STATUS_OK: u16 = 0x0000;
+const STATUS_KEY_NOT_FOUND: u16 = 0x0001;
+const STATUS_KEY_EXISTS: u16 = 0x0002;
+const STATUS_ITEM_NOT_STORED: u16 = 0x0005;
+const STATUS_NON_NUMERIC: u16 = 0x0006;
+const STATUS_INVALID_ARGUMENTS: u16 = 0x0004;
+const STATUS_INTERNAL_ERROR: u16 = 0x0084;
+
+pub struct TcpTransport<S: Read + Write> {
+    stream: S,
+    outgoing_buffer: Vec<u8>,
+    peeked_byte: Option<u8>,
+}
+
+impl<S: Read + Write> TcpTransport<S> {
+    pub fn new(stream: S) -> TcpTransport<S> {
+        TcpTransport {
+            stream: stream,
+            outgoing_buffer: Vec::new(),
+            peeked_byte: None,
+        }
+    }
+
+    pub fn get_stream(&self) -> &S {
+        &self.stream
+    }
+
+    pub fn get_outgoing_buffer(&self) -> &[u8] {
+        &self.outgoing_buffer
+    }
+
+
+    // Basic methods to consume the stream
+
+    pub fn as_string(&self, bytes: Vec<u8>) -> Result<String, Error> {
+        String::from_utf8(bytes).map_err(|_| Error::Utf8)
+    }
+
+    pub fn as_number<T: FromStr>(&self, bytes: Vec<u8>) -> Result<T, Error> {
+        let s = self.as_string(bytes)?;
+        s.parse::<T>().map_err(|_| Error::NumberParse)
+    }
+
+    fn read_byte_raw(&mut self) -> Result<u8, Error> {
+        let mut buf = [0u8; 1];
+        self.stream
+            .read_exact(&mut buf)
+            .map_err(|_| Error::UnexpectedEof { expected: 1, got: 0 })?;
+        Ok(buf[0])
+    }
+
+    pub fn read_byte(&mut self) -> Result<u8, Error> {
+        match self.peeked_byte.take() {
+            Some(byte) => Ok(byte),
+            None => self.read_byte_raw(),
+        }
+    }
+
+    // Looks at the next byte without consuming it, so callers can decide
+    // which protocol a connection is speaking before committing to a parse.
+    fn peek_byte(&mut self) -> Result<u8, Error> {
+        if self.peeked_byte.is_none() {
+            self.peeked_byte = Some(self.read_byte_raw()?);
+        }
+        Ok(self.peeked_byte.unwrap())
+    }
+
+    // Reads exactly `n` bytes, reporting precisely how far the stream got
+    // before it ran dry rather than just "some read failed".
+    pub fn read_bytes(&mut self, n: usize) -> Result<Vec<u8>, Error> {
+        let mut bytes = Vec::with_capacity(n);
+        for i in 0..n {
+            match self.read_byte() {
+                Ok(byte) => bytes.push(byte),
+                Err(_) => return Err(Error::UnexpectedEof { expected: n, got: i }),
+            }
+        }
+        Ok(bytes)
+    }
+
+    // Reads up to `limit` bytes looking for a `\r\n` terminator, returning
+    // the line without it. Errors with `BadLineTerminator` on a `\n` not
+    // preceded by `\r`, and with `LineTooLong` if `limit` bytes are
+    // consumed without finding a terminator.
+    pub fn read_line(&mut self, limit: usize) -> Result<Vec<u8>, Error> {
+        let mut line = Vec::new();
+        let mut prev: Option<u8> = None;
+
+        for _ in 0..limit {
+            let byte = self.read_byte()?;
+
+            if byte == 10 {
+                if prev != Some(13) {
+                    return Err(Error::BadLineTerminator);
+                }
+                line.pop();
+                return Ok(line);
+            }
+
+            line.push(byte);
+            prev = Some(byte);
+        }
+
+        Err(Error::LineTooLong { limit: limit })
+    }
+
+    // Splits `bytes` on the first space, handing back the word before it
+    // and everything from the space onwards (the caller strips the
+    // separator itself before parsing the next word).
+    pub fn parse_word(&self, bytes: Vec<u8>) -> Result<(Vec<u8>, Vec<u8>), Error> {
+        match bytes.iter().position(|&b| b == 32) {
+            Some(i) => Ok((bytes[..i].to_vec(), bytes[i..].to_vec())),
+            None => Ok((bytes, Vec::new())),
+        }
+    }
+
+    fn next_word(&self, rest: Vec<u8>) -> Result<(Vec<u8>, Vec<u8>), Error> {
+        if rest.first() != Some(&32) {
+            return Err(Error::CommandParse);
+        }
+        self.parse_word(rest[1..].to_vec())
+    }
+
+
+    // Basic methods to produce the stream
+
+    pub fn write_bytes(&mut self, bytes: &[u8]) -> Result<usize, Error> {
+        self.outgoing_buffer.extend_from_slice(bytes);
+        Ok(bytes.len())
+    }
+
+    pub fn write_string(&mut self, s: &str) -> Result<usize, Error> {
+        self.write_bytes(s.as_bytes())
+    }
+
+    pub fn flush_writes(&mut self) -> Result<(), Error> {
+        self.stream
+            .write_all(&self.outgoing_buffer)
+            .map_err(|_| Error::StreamWrite)?;
+        self.outgoing_buffer.clear();
+        Ok(())
+    }
+
+
+    // Command parsing: dispatches to whichever protocol the client is
+    // speaking, peeking the first byte so the stream isn't disturbed
+    // for the protocol that doesn't end up being read.
+    pub fn read_cmd(&mut self) -> Result<Cmd, Error> {
+        let magic = self.peek_byte()?;
+
+        if magic == BINARY_REQUEST_MAGIC {
+            self.read_cmd_binary()
+        } else {
+            self.read_cmd_text()
+        }
+    }
+
+    fn read_cmd_text(&mut self) -> Result<Cmd, Error> {
+        let line = self.read_line(LINE_MAXLEN)?;
+        let (verb_bytes, rest) = self.parse_word(line)?;
+        let verb = self.as_string(verb_bytes)?;
+
+        match verb.as_str() {
+            "get" => self.read_cmd_get(rest, false),
+            "gets" => self.read_cmd_get(rest, true),
+            "set" => Ok(Cmd::Set(self.read_set_body(rest)?)),
+            "add" => Ok(Cmd::Add(self.read_set_body(rest)?)),
+            "replace" => Ok(Cmd::Replace(self.read_set_body(rest)?)),
+            "append" => Ok(Cmd::Append(self.read_set_body(rest)?)),
+            "prepend" => Ok(Cmd::Prepend(self.read_set_body(rest)?)),
+            "cas" => self.read_cmd_cas(rest),
+            "delete" => self.read_cmd_delete(rest),
+            "incr" => {
+                let (key, delta) = self.read_cmd_incr_decr_body(rest)?;
+                Ok(Cmd::Incr(IncrDecr::new(key, delta)))
+            }
+            "decr" => {
+                let (key, delta) = self.read_cmd_incr_decr_body(rest)?;
+                Ok(Cmd::Decr(IncrDecr::new(key, delta)))
+            }
+            "flush_all" => self.read_cmd_flush_all(rest),
+            "stats" => {
+                if !rest.is_empty() {
+                    return Err(Error::CommandParse);
+                }
+                Ok(Cmd::Stats)
+            }
+            _ => Err(Error::InvalidCommand { verb: Verb::new(verb.as_bytes()) }),
+        }
+    }
+
+    fn read_cmd_get(&mut self, rest: Vec<u8>, with_cas: bool) -> Result<Cmd, Error> {
+        let (key_bytes, rest) = self.next_word(rest)?;
+        if !rest.is_empty() {
+            return Err(Error::CommandParse);
+        }
+
+        let key = self.as_string(key_bytes)?;
+        let get = if with_cas { Get::gets(key) } else { Get::new(key) };
+        Ok(Cmd::Get(get))
+    }
+
+    // Reads a `data_len`-byte payload followed by its `\r\n` terminator,
+    // shared by the `set`-shaped commands and `cas`. Rejects `data_len`
+    // against `DATA_MAXLEN` up front so a bogus or overflowing declared
+    // length never reaches the `+ 2` arithmetic or the read itself.
+    fn read_framed_payload(&mut self, data_len: usize) -> Result<Vec<u8>, Error> {
+        if data_len > DATA_MAXLEN {
+            return Err(Error::ValueTooLong { limit: DATA_MAXLEN as u64, got: data_len as u64 });
+        }
+
+        let mut payload = self.read_bytes(data_len + 2)?;
+        let terminator = payload.split_off(data_len);
+        if terminator != [13, 10] {
+            return Err(Error::BadLineTerminator);
+        }
+
+        Ok(payload)
+    }
+
+    // Shared body parser for the `set`-shaped commands (`set`, `add`,
+    // `replace`, `append`, `prepend`): same wire format, different Cmd
+    // variant depending on which verb the caller matched on.
+    fn read_set_body(&mut self, rest: Vec<u8>) -> Result<Set, Error> {
+        let (key_bytes, rest) = self.next_word(rest)?;
+        let (flags_bytes, rest) = self.next_word(rest)?;
+        let (exptime_bytes, rest) = self.next_word(rest)?;
+        let (len_bytes, rest) = self.next_word(rest)?;
+        if !rest.is_empty() {
+            return Err(Error::CommandParse);
+        }
+
+        let key = self.as_string(key_bytes)?;
+        let flags = self.as_number::<u32>(flags_bytes)?;
+        let exptime = self.as_number::<f64>(exptime_bytes)?;
+        let data_len = self.as_number::<usize>(len_bytes)?;
+
+        let payload = self.read_framed_payload(data_len)?;
+
+        let mut set = Set::new(key, flags, payload);
+        set.with_exptime(exptime);
+        Ok(set)
+    }
+
+    fn read_cmd_delete(&mut self, rest: Vec<u8>) -> Result<Cmd, Error> {
+        let (key_bytes, rest) = self.next_word(rest)?;
+        if !rest.is_empty() {
+            return Err(Error::CommandParse);
+        }
+
+        let key = self.as_string(key_bytes)?;
+        Ok(Cmd::Delete(Delete::new(key)))
+    }
+
+    fn read_cmd_incr_decr_body(&mut self, rest: Vec<u8>) -> Result<(String, u64), Error> {
+        let (key_bytes, rest) = self.next_word(rest)?;
+        let (delta_bytes, rest) = self.next_word(rest)?;
+        if !rest.is_empty() {
+            return Err(Error::CommandParse);
+        }
+
+        let key = self.as_string(key_bytes)?;
+        let delta = self.as_number::<u64>(delta_bytes)?;
+        Ok((key, delta))
+    }
+
+    fn read_cmd_flush_all(&mut self, rest: Vec<u8>) -> Result<Cmd, Error> {
+        if rest.is_empty() {
+            return Ok(Cmd::FlushAll(FlushAll::new()));
+        }
+
+        let (delay_bytes, rest) = self.next_word(rest)?;
+        if !rest.is_empty() {
+            return Err(Error::CommandParse);
+        }
+
+        let delay = self.as_number::<f64>(delay_bytes)?;
+        let mut flush = FlushAll::new();
+        flush.with_delay(delay);
+        Ok(Cmd::FlushAll(flush))
+    }
+
+    fn read_cmd_cas(&mut self, rest: Vec<u8>) -> Result<Cmd, Error> {
+        let (key_bytes, rest) = self.next_word(rest)?;
+        let (flags_bytes, rest) = self.next_word(rest)?;
+        let (exptime_bytes, rest) = self.next_word(rest)?;
+        let (len_bytes, rest) = self.next_word(rest)?;
+        let (cas_bytes, rest) = self.next_word(rest)?;
+        if !rest.is_empty() {
+            return Err(Error::CommandParse);
+        }
+
+        let key = self.as_string(key_bytes)?;
+        let flags = self.as_number::<u32>(flags_bytes)?;
+        let exptime = self.as_number::<f64>(exptime_bytes)?;
+        let data_len = self.as_number::<usize>(len_bytes)?;
+        let cas_unique = self.as_number::<u64>(cas_bytes)?;
+
+        let payload = self.read_framed_payload(data_len)?;
+
+        let mut cas = Cas::new(key, flags, payload, cas_unique);
+        cas.with_exptime(exptime);
+        Ok(Cmd::Cas(cas))
+    }
+
+
+    // Binary protocol: a fixed 24-byte header (magic, opcode, key length,
+    // extras length, data type, vbucket/status, total body length,
+    // opaque, CAS) followed by `extras || key || value`.
+    fn read_cmd_binary(&mut self) -> Result<Cmd, Error> {
+        let header = self.read_bytes(24)?;
+
+        if header[0] != BINARY_REQUEST_MAGIC {
+            return Err(Error::InvalidCommand { verb: Verb::new(&header[0..1]) });
+        }
+
+        let opcode = header[1];
+        let key_len = ((header[2] as usize) << 8) | header[3] as usize;
+        let extras_len = header[4] as usize;
+        let total_body_len = ((header[8] as usize) << 24)
+            | ((header[9] as usize) << 16)
+            | ((header[10] as usize) << 8)
+            | header[11] as usize;
+
+        let value_len = total_body_len
+            .checked_sub(key_len + extras_len)
+            .ok_or(Error::CommandParse)?;
+
+        // A non-zero CAS in the header requests a conditional store; zero
+        // means "store unconditionally", matching the real binary protocol.
+        let cas = Self::be_u64(&header[16..24]);
+
+        // Bound-check before allocating: `total_body_len` comes straight off
+        // the wire and can declare up to ~4.3 GB, same class of bug that
+        // `read_framed_payload` guards against for the text protocol.
+        if total_body_len > DATA_MAXLEN {
+            return Err(Error::ValueTooLong {
+                limit: DATA_MAXLEN as u64,
+                got: total_body_len as u64,
+            });
+        }
+
+        let body = self.read_bytes(total_body_len)?;
+        let extras = &body[0..extras_len];
+        let key_bytes = body[extras_len..extras_len + key_len].to_vec();
+        let value = body[extras_len + key_len..extras_len + key_len + value_len].to_vec();
+
+        match opcode {
+            OPCODE_GET => {
+                let key = self.as_string(key_bytes)?;
+                Ok(Cmd::Get(Get::new(key)))
+            }
+            OPCODE_SET => {
+                if extras.len() != 8 {
+                    return Err(Error::CommandParse);
+                }
+                let flags = ((extras[0] as u32) << 24)
+                    | ((extras[1] as u32) << 16)
+                    | ((extras[2] as u32) << 8)
+                    | extras[3] as u32;
+                let exptime = ((extras[4] as u32) << 24)
+                    | ((extras[5] as u32) << 16)
+                    | ((extras[6] as u32) << 8)
+                    | extras[7] as u32;
+
+                let key = self.as_string(key_bytes)?;
+
+                if cas != 0 {
+                    let mut req = Cas::new(key, flags, value, cas);
+                    req.with_exptime(exptime as f64);
+                    Ok(Cmd::Cas(req))
+                } else {
+                    let mut set = Set::new(key, flags, value);
+                    set.with_exptime(exptime as f64);
+                    Ok(Cmd::Set(set))
+                }
+            }
+            OPCODE_ADD | OPCODE_REPLACE => {
+                if extras.len() != 8 {
+                    return Err(Error::CommandParse);
+                }
+                let flags = ((extras[0] as u32) << 24)
+                    | ((extras[1] as u32) << 16)
+                    | ((extras[2] as u32) << 8)
+                    | extras[3] as u32;
+                let exptime = ((extras[4] as u32) << 24)
+                    | ((extras[5] as u32) << 16)
+                    | ((extras[6] as u32) << 8)
+                    | extras[7] as u32;
+
+                let key = self.as_string(key_bytes)?;
+                let mut set = Set::new(key, flags, value);
+                set.with_exptime(exptime as f64);
+                if opcode == OPCODE_ADD {
+                    Ok(Cmd::Add(set))
+                } else {
+                    Ok(Cmd::Replace(set))
+                }
+            }
+            OPCODE_APPEND => {
+                let key = self.as_string(key_bytes)?;
+                Ok(Cmd::Append(Set::new(key, 0, value)))
+            }
+            OPCODE_PREPEND => {
+                let key = self.as_string(key_bytes)?;
+                Ok(Cmd::Prepend(Set::new(key, 0, value)))
+            }
+            OPCODE_DELETE => {
+                let key = self.as_string(key_bytes)?;
+                Ok(Cmd::Delete(Delete::new(key)))
+            }
+            OPCODE_INCREMENT | OPCODE_DECREMENT => {
+                // Real binary incr/decr extras also carry an initial value
+                // and expiration for create-on-miss semantics; this cache's
+                // incr/decr always errors on a missing key, so only the
+                // delta is used.
+                if extras.len() != 20 {
+                    return Err(Error::CommandParse);
+                }
+                let delta = Self::be_u64(&extras[0..8]);
+                let key = self.as_string(key_bytes)?;
+                let incr_decr = IncrDecr::new(key, delta);
+                if opcode == OPCODE_INCREMENT {
+                    Ok(Cmd::Incr(incr_decr))
+                } else {
+                    Ok(Cmd::Decr(incr_decr))
+                }
+            }
+            OPCODE_FLUSH => {
+                let delay = if extras.len() == 4 {
+                    (((extras[0] as u32) << 24)
+                        | ((extras[1] as u32) << 16)
+                        | ((extras[2] as u32) << 8)
+                        | extras[3] as u32) as f64
+                } else {
+                    0.0
+                };
+                let mut flush = FlushAll::new();
+                flush.with_delay(delay);
+                Ok(Cmd::FlushAll(flush))
+            }
+            OPCODE_STAT => Ok(Cmd::Stats),
+            _ => Err(Error::InvalidCommand { verb: Verb::new(&[opcode]) }),
+        }
+    }
+
+    fn be_u64(bytes: &[u8]) -> u64 {
+        let mut n: u64 = 0;
+        for &byte in bytes {
+            n = (n << 8) | byte as u64;
+        }
+        n
+    }
+
+
+    // Response writing
+
+    pub fn write_resp(&mut self, resp: &Resp) -> Result<(), Error> {
+        match *resp {
+            Resp::Error => {
+                self.write_string("ERROR\r\n")?;
+            }
+            Resp::Stored => {
+                self.write_string("STORED\r\n")?;
+            }
+            Resp::Exists => {
+                self.write_string("EXISTS\r\n")?;
+            }
+            Resp::NotFound => {
+                self.write_string("NOT_FOUND\r\n")?;
+            }
+            Resp::NotStored => {
+                self.write_string("NOT_STORED\r\n")?;
+            }
+            Resp::Deleted => {
+                self.write_string("DELETED\r\n")?;
+            }
+            Resp::Number(n) => {
+                self.write_string(&format!("{}\r\n", n))?;
+            }
+            Resp::ClientError(ref message) => {
+                self.write_string(&format!("CLIENT_ERROR {}\r\n", message))?;
+            }
+            Resp::NotNumeric => {
+                self.write_string("CLIENT_ERROR cannot increment or decrement non-numeric value\r\n")?;
+            }
+            Resp::ServerError(ref message) => {
+                self.write_string(&format!("SERVER_ERROR {}\r\n", message))?;
+            }
+            Resp::Ok => {
+                self.write_string("OK\r\n")?;
+            }
+            Resp::Value(ref value) => {
+                let header = match value.cas_unique {
+                    Some(cas) => format!(
+                        "VALUE {} {} {} {}\r\n",
+                        value.key,
+                        value.flags,
+                        value.data.len(),
+                        cas
+                    ),
+                    None => format!("VALUE {} {} {}\r\n", value.key, value.flags, value.data.len()),
+                };
+                self.write_string(&header)?;
+                self.write_bytes(&value.data)?;
+                self.write_string("\r\n")?;
+            }
+            Resp::Stats(ref stats) => {
+                for stat in stats {
+                    self.write_string(&format!("{} {}\r\n", stat.name, stat.value))?;
+                }
+                self.write_string("END\r\n")?;
+            }
+        }
+
+        self.flush_writes()
+    }
+
+    fn status_for(resp: &Resp) -> u16 {
+        match *resp {
+            Resp::Error => STATUS_INTERNAL_ERROR,
+            Resp::NotFound => STATUS_KEY_NOT_FOUND,
+            Resp::Exists => STATUS_KEY_EXISTS,
+            Resp::NotStored => STATUS_ITEM_NOT_STORED,
+            Resp::ClientError(_) => STATUS_INVALID_ARGUMENTS,
+            Resp::NotNumeric => STATUS_NON_NUMERIC,
+            Resp::ServerError(_) => STATUS_INTERNAL_ERROR,
+            Resp::Stored
+            | Resp::Value(_)
+            | Resp::Stats(_)
+            | Resp::Deleted
+            | Resp::Number(_)
+            | Resp::Ok => STATUS_OK,
+        }
+    }
+
+    fn opcode_for(resp: &Resp, fallback_opcode: u8) -> u8 {
+        match *resp {
+            Resp::Stats(_) => OPCODE_STAT,
+            _ => fallback_opcode,
+        }
+    }
+
+    // Mirrors `write_resp` for binary-protocol connections: emits the
+    // 24-byte response header (magic 0x81) with the opcode and opaque
+    // echoed back from the request that triggered this response, then
+    // `extras || key || value` as appropriate for the response kind.
+    pub fn write_resp_binary(
+        &mut self,
+        resp: &Resp,
+        opcode: u8,
+        opaque: [u8; 4],
+    ) -> Result<(), Error> {
+        let status = Self::status_for(resp);
+        let opcode = Self::opcode_for(resp, opcode);
+
+        let (extras, value): (Vec<u8>, Vec<u8>) = match *resp {
+            Resp::Value(ref v) => {
+                let mut extras = Vec::with_capacity(4);
+                extras.extend_from_slice(&[
+                    (v.flags >> 24) as u8,
+                    (v.flags >> 16) as u8,
+                    (v.flags >> 8) as u8,
+                    v.flags as u8,
+                ]);
+                (extras, v.data.clone())
+            }
+            Resp::Number(n) => {
+                let value = vec![
+                    (n >> 56) as u8,
+                    (n >> 48) as u8,
+                    (n >> 40) as u8,
+                    (n >> 32) as u8,
+                    (n >> 24) as u8,
+                    (n >> 16) as u8,
+                    (n >> 8) as u8,
+                    n as u8,
+                ];
+                (Vec::new(), value)
+            }
+            _ => (Vec::new(), Vec::new()),
+        };
+
+        let total_body_len = extras.len() + value.len();
+
+        let mut header = Vec::with_capacity(24);
+        header.push(BINARY_RESPONSE_MAGIC);
+        header.push(opcode);
+        header.extend_from_slice(&[0, 0]); // key length: responses never echo the key
+        header.push(extras.len() as u8);
+        header.push(0x00); // data type
+        header.extend_from_slice(&[(status >> 8) as u8, status as u8]);
+        header.extend_from_slice(&[
+            (total_body_len >> 24) as u8,
+            (total_body_len >> 16) as u8,
+            (total_body_len >> 8) as u8,
+            total_body_len as u8,
+        ]);
+        header.extend_from_slice(&opaque);
+
+        let cas = match *resp {
+            Resp::Value(ref v) => v.cas_unique.unwrap_or(0),
+            _ => 0,
+        };
+        header.extend_from_slice(&[
+            (cas >> 56) as u8,
+            (cas >> 48) as u8,
+            (cas >> 40) as u8,
+            (cas >> 32) as u8,
+            (cas >> 24) as u8,
+            (cas >> 16) as u8,
+            (cas >> 8) as u8,
+            cas as u8,
+        ]);
+
+        self.write_bytes(&header)?;
+        self.write_bytes(&extras)?;
+        self.write_bytes(&value)?;
+        self.flush_writes()
+    }
+}
+
+impl<S: Read + Write> SyncTransport for TcpTransport<S> {
+    fn read_cmd(&mut self) -> Result<Cmd, Error> {
+        TcpTransport::read_cmd(self)
+    }
+
+    fn write_resp(&mut self, resp: &Resp) -> Result<(), Error> {
+        TcpTransport::write_resp(self, resp)
+    }
+
+    fn flush_writes(&mut self) -> Result<(), Error> {
+        TcpTransport::flush_writes(self)
+    }
+}
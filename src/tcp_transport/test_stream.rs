@@ -0,0 +1,60 @@
+use std::cmp;
+use std::io::{self, Read, Write};
+
+/// An in-memory stream stand-in for a `TcpStream`, used to drive
+/// `TcpTransport` in tests without touching a real socket.
+pub struct TestStream {
+    incoming: Vec<u8>,
+    pos: usize,
+    pub outgoing: Vec<u8>,
+    chunk_size: Option<usize>,
+}
+
+impl TestStream {
+    pub fn new(incoming: Vec<u8>) -> TestStream {
+        TestStream {
+            incoming: incoming,
+            pos: 0,
+            outgoing: Vec::new(),
+            chunk_size: None,
+        }
+    }
+
+    /// Like `new`, but each `read` hands back at most `chunk_size` bytes
+    /// regardless of how much the caller's buffer could hold, so a
+    /// partial-parse state machine can be driven across several reads
+    /// deterministically instead of getting the whole command at once.
+    pub fn new_chunked(incoming: Vec<u8>, chunk_size: usize) -> TestStream {
+        TestStream {
+            incoming: incoming,
+            pos: 0,
+            outgoing: Vec::new(),
+            chunk_size: Some(chunk_size),
+        }
+    }
+}
+
+impl Read for TestStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remaining = &self.incoming[self.pos..];
+        let cap = match self.chunk_size {
+            Some(chunk_size) => cmp::min(chunk_size, buf.len()),
+            None => buf.len(),
+        };
+        let n = cmp::min(remaining.len(), cap);
+        buf[..n].copy_from_slice(&remaining[..n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+impl Write for TestStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.outgoing.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
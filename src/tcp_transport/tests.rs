@@ -1,9 +1,16 @@
+use super::DATA_MAXLEN;
 use super::TcpTransport;
-use super::TcpTransportError;
 use super::test_stream::TestStream;
 
+use error::Error;
+use error::Verb;
+
+use protocol::cmd::Cas;
 use protocol::cmd::Cmd;
+use protocol::cmd::Delete;
+use protocol::cmd::FlushAll;
 use protocol::cmd::Get;
+use protocol::cmd::IncrDecr;
 use protocol::cmd::Resp;
 use protocol::cmd::Set;
 use protocol::cmd::Stat;
@@ -28,7 +35,7 @@ fn test_as_string_invalid() {
 
     // Invalid utf8 bytes
     let err = transport.as_string(vec![97, 254, 255]).unwrap_err();
-    assert_eq!(err, TcpTransportError::Utf8Error);
+    assert_eq!(err, Error::Utf8);
 }
 
 #[test]
@@ -48,7 +55,7 @@ fn test_as_number_invalid() {
 
     let bytes = "12 3".to_string().into_bytes();
     let err = transport.as_number::<u32>(bytes).unwrap_err();
-    assert_eq!(err, TcpTransportError::NumberParseError);
+    assert_eq!(err, Error::NumberParse);
 }
 
 #[test]
@@ -84,7 +91,7 @@ fn test_read_line_invalid_newline_marker() {
     let mut transport = TcpTransport::new(ts);
 
     let err = transport.read_line(2).unwrap_err();
-    assert_eq!(err, TcpTransportError::LineReadError);
+    assert_eq!(err, Error::BadLineTerminator);
 }
 
 #[test]
@@ -93,7 +100,7 @@ fn test_read_line_too_long() {
     let mut transport = TcpTransport::new(ts);
 
     let err = transport.read_line(5).unwrap_err();
-    assert_eq!(err, TcpTransportError::LineReadError);
+    assert_eq!(err, Error::LineTooLong { limit: 5 });
 }
 
 #[test]
@@ -156,7 +163,7 @@ fn test_read_cmd_invalid() {
     let mut transport = TcpTransport::new(ts);
 
     let err = transport.read_cmd().unwrap_err();
-    assert_eq!(err, TcpTransportError::InvalidCmd);
+    assert_eq!(err, Error::InvalidCommand { verb: Verb::new(b"invalid") });
 }
 
 #[test]
@@ -166,7 +173,7 @@ fn test_read_cmd_malterminated() {
     let mut transport = TcpTransport::new(ts);
 
     let err = transport.read_cmd().unwrap_err();
-    assert_eq!(err, TcpTransportError::StreamReadError);
+    assert_eq!(err, Error::BadLineTerminator);
 }
 
 
@@ -189,7 +196,7 @@ fn test_read_cmd_get_malformed() {
     let mut transport = TcpTransport::new(ts);
 
     let err = transport.read_cmd().unwrap_err();
-    assert_eq!(err, TcpTransportError::CommandParseError);
+    assert_eq!(err, Error::CommandParse);
 }
 
 #[test]
@@ -200,7 +207,7 @@ fn test_read_cmd_get_non_utf8() {
     let mut transport = TcpTransport::new(ts);
 
     let err = transport.read_cmd().unwrap_err();
-    assert_eq!(err, TcpTransportError::Utf8Error);
+    assert_eq!(err, Error::Utf8);
 }
 
 
@@ -223,7 +230,7 @@ fn test_read_cmd_set_under_size() {
     let mut transport = TcpTransport::new(ts);
 
     let err = transport.read_cmd().unwrap_err();
-    assert_eq!(err, TcpTransportError::CommandParseError);
+    assert_eq!(err, Error::BadLineTerminator);
 }
 
 #[test]
@@ -233,7 +240,290 @@ fn test_read_cmd_set_over_size() {
     let mut transport = TcpTransport::new(ts);
 
     let err = transport.read_cmd().unwrap_err();
-    assert_eq!(err, TcpTransportError::StreamReadError);
+    assert_eq!(err, Error::UnexpectedEof { expected: 6, got: 5 });
+}
+
+#[test]
+fn test_read_cmd_set_declared_length_overflow_rejected() {
+    // A declared length near usize::MAX must not reach the `data_len + 2`
+    // read size unchecked, and must not panic on the subsequent split_off.
+    let cmd_str = "set x 0 0 18446744073709551615\r\nA\r\n".to_string();
+    let ts = TestStream::new(cmd_str.into_bytes());
+    let mut transport = TcpTransport::new(ts);
+
+    let err = transport.read_cmd().unwrap_err();
+    assert_eq!(err, Error::ValueTooLong { limit: DATA_MAXLEN as u64, got: 18446744073709551615 });
+}
+
+
+// Command parsing: Gets/Cas
+
+#[test]
+fn test_read_cmd_gets_ok() {
+    let cmd_str = "gets x\r\n".to_string();
+    let ts = TestStream::new(cmd_str.into_bytes());
+    let mut transport = TcpTransport::new(ts);
+
+    let cmd = transport.read_cmd().unwrap();
+    match cmd {
+        Cmd::Get(get) => {
+            assert_eq!(get.key, "x");
+            assert!(get.with_cas);
+        }
+        other => panic!("expected Cmd::Get, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_read_cmd_cas_ok() {
+    let cmd_str = "cas x 0 0 3 42\r\nabc\r\n".to_string();
+    let ts = TestStream::new(cmd_str.into_bytes());
+    let mut transport = TcpTransport::new(ts);
+
+    let cmd = transport.read_cmd().unwrap();
+    assert_eq!(cmd, Cmd::Cas(Cas::new("x", 0, vec![97, 98, 99], 42)));
+}
+
+#[test]
+fn test_read_cmd_cas_invalid_token() {
+    let cmd_str = "cas x 0 0 3 abc\r\nabc\r\n".to_string();
+    let ts = TestStream::new(cmd_str.into_bytes());
+    let mut transport = TcpTransport::new(ts);
+
+    let err = transport.read_cmd().unwrap_err();
+    assert_eq!(err, Error::NumberParse);
+}
+
+#[test]
+fn test_read_cmd_cas_declared_length_overflow_rejected() {
+    let cmd_str = "cas x 0 0 18446744073709551615 42\r\nA\r\n".to_string();
+    let ts = TestStream::new(cmd_str.into_bytes());
+    let mut transport = TcpTransport::new(ts);
+
+    let err = transport.read_cmd().unwrap_err();
+    assert_eq!(err, Error::ValueTooLong { limit: DATA_MAXLEN as u64, got: 18446744073709551615 });
+}
+
+
+// Command parsing: add / replace / append / prepend
+
+#[test]
+fn test_read_cmd_add_ok() {
+    let cmd_str = "add x 0 0 3\r\nabc\r\n".to_string();
+    let ts = TestStream::new(cmd_str.into_bytes());
+    let mut transport = TcpTransport::new(ts);
+
+    let cmd = transport.read_cmd().unwrap();
+    assert_eq!(cmd, Cmd::Add(Set::new("x", 0, vec![97, 98, 99])));
+}
+
+#[test]
+fn test_read_cmd_replace_ok() {
+    let cmd_str = "replace x 0 0 3\r\nabc\r\n".to_string();
+    let ts = TestStream::new(cmd_str.into_bytes());
+    let mut transport = TcpTransport::new(ts);
+
+    let cmd = transport.read_cmd().unwrap();
+    assert_eq!(cmd, Cmd::Replace(Set::new("x", 0, vec![97, 98, 99])));
+}
+
+#[test]
+fn test_read_cmd_append_ok() {
+    let cmd_str = "append x 0 0 3\r\nabc\r\n".to_string();
+    let ts = TestStream::new(cmd_str.into_bytes());
+    let mut transport = TcpTransport::new(ts);
+
+    let cmd = transport.read_cmd().unwrap();
+    assert_eq!(cmd, Cmd::Append(Set::new("x", 0, vec![97, 98, 99])));
+}
+
+#[test]
+fn test_read_cmd_prepend_ok() {
+    let cmd_str = "prepend x 0 0 3\r\nabc\r\n".to_string();
+    let ts = TestStream::new(cmd_str.into_bytes());
+    let mut transport = TcpTransport::new(ts);
+
+    let cmd = transport.read_cmd().unwrap();
+    assert_eq!(cmd, Cmd::Prepend(Set::new("x", 0, vec![97, 98, 99])));
+}
+
+
+// Command parsing: delete
+
+#[test]
+fn test_read_cmd_delete_ok() {
+    let cmd_str = "delete x\r\n".to_string();
+    let ts = TestStream::new(cmd_str.into_bytes());
+    let mut transport = TcpTransport::new(ts);
+
+    let cmd = transport.read_cmd().unwrap();
+    assert_eq!(cmd, Cmd::Delete(Delete::new("x")));
+}
+
+
+// Command parsing: incr / decr
+
+#[test]
+fn test_read_cmd_incr_ok() {
+    let cmd_str = "incr x 5\r\n".to_string();
+    let ts = TestStream::new(cmd_str.into_bytes());
+    let mut transport = TcpTransport::new(ts);
+
+    let cmd = transport.read_cmd().unwrap();
+    assert_eq!(cmd, Cmd::Incr(IncrDecr::new("x", 5)));
+}
+
+#[test]
+fn test_read_cmd_decr_ok() {
+    let cmd_str = "decr x 5\r\n".to_string();
+    let ts = TestStream::new(cmd_str.into_bytes());
+    let mut transport = TcpTransport::new(ts);
+
+    let cmd = transport.read_cmd().unwrap();
+    assert_eq!(cmd, Cmd::Decr(IncrDecr::new("x", 5)));
+}
+
+
+// Command parsing: flush_all
+
+#[test]
+fn test_read_cmd_flush_all_without_delay() {
+    let cmd_str = "flush_all\r\n".to_string();
+    let ts = TestStream::new(cmd_str.into_bytes());
+    let mut transport = TcpTransport::new(ts);
+
+    let cmd = transport.read_cmd().unwrap();
+    assert_eq!(cmd, Cmd::FlushAll(FlushAll::new()));
+}
+
+#[test]
+fn test_read_cmd_flush_all_with_delay() {
+    let cmd_str = "flush_all 30\r\n".to_string();
+    let ts = TestStream::new(cmd_str.into_bytes());
+    let mut transport = TcpTransport::new(ts);
+
+    let cmd = transport.read_cmd().unwrap();
+    let mut expected = FlushAll::new();
+    expected.with_delay(30.0);
+    assert_eq!(cmd, Cmd::FlushAll(expected));
+}
+
+
+// Response writing: gets VALUE with a CAS token
+
+#[test]
+fn test_write_resp_value_with_cas() {
+    let ts = TestStream::new(vec![]);
+    let mut transport = TcpTransport::new(ts);
+
+    let mut value = Value::new("x", "abc".to_string().into_bytes());
+    value.with_cas_unique(42);
+    let resp = Resp::Value(value);
+    transport.write_resp(&resp).unwrap();
+    let expected = "VALUE x 0 3 42\r\nabc\r\n".to_string().into_bytes();
+    assert_eq!(transport.get_stream().outgoing, expected);
+}
+
+
+// Response writing: Exists / NotFound
+
+#[test]
+fn test_write_resp_exists() {
+    let ts = TestStream::new(vec![]);
+    let mut transport = TcpTransport::new(ts);
+
+    transport.write_resp(&Resp::Exists).unwrap();
+    let expected = "EXISTS\r\n".to_string().into_bytes();
+    assert_eq!(transport.get_stream().outgoing, expected);
+}
+
+#[test]
+fn test_write_resp_not_found() {
+    let ts = TestStream::new(vec![]);
+    let mut transport = TcpTransport::new(ts);
+
+    transport.write_resp(&Resp::NotFound).unwrap();
+    let expected = "NOT_FOUND\r\n".to_string().into_bytes();
+    assert_eq!(transport.get_stream().outgoing, expected);
+}
+
+
+// Response writing: NotStored / Deleted / Number / ClientError / Ok
+
+#[test]
+fn test_write_resp_not_stored() {
+    let ts = TestStream::new(vec![]);
+    let mut transport = TcpTransport::new(ts);
+
+    transport.write_resp(&Resp::NotStored).unwrap();
+    let expected = "NOT_STORED\r\n".to_string().into_bytes();
+    assert_eq!(transport.get_stream().outgoing, expected);
+}
+
+#[test]
+fn test_write_resp_deleted() {
+    let ts = TestStream::new(vec![]);
+    let mut transport = TcpTransport::new(ts);
+
+    transport.write_resp(&Resp::Deleted).unwrap();
+    let expected = "DELETED\r\n".to_string().into_bytes();
+    assert_eq!(transport.get_stream().outgoing, expected);
+}
+
+#[test]
+fn test_write_resp_number() {
+    let ts = TestStream::new(vec![]);
+    let mut transport = TcpTransport::new(ts);
+
+    transport.write_resp(&Resp::Number(42)).unwrap();
+    let expected = "42\r\n".to_string().into_bytes();
+    assert_eq!(transport.get_stream().outgoing, expected);
+}
+
+#[test]
+fn test_write_resp_client_error() {
+    let ts = TestStream::new(vec![]);
+    let mut transport = TcpTransport::new(ts);
+
+    transport.write_resp(&Resp::ClientError("malformed command".to_string())).unwrap();
+    let expected = "CLIENT_ERROR malformed command\r\n"
+        .to_string()
+        .into_bytes();
+    assert_eq!(transport.get_stream().outgoing, expected);
+}
+
+#[test]
+fn test_write_resp_not_numeric() {
+    let ts = TestStream::new(vec![]);
+    let mut transport = TcpTransport::new(ts);
+
+    transport.write_resp(&Resp::NotNumeric).unwrap();
+    let expected = "CLIENT_ERROR cannot increment or decrement non-numeric value\r\n"
+        .to_string()
+        .into_bytes();
+    assert_eq!(transport.get_stream().outgoing, expected);
+}
+
+#[test]
+fn test_write_resp_server_error() {
+    let ts = TestStream::new(vec![]);
+    let mut transport = TcpTransport::new(ts);
+
+    transport.write_resp(&Resp::ServerError("cache is at capacity".to_string())).unwrap();
+    let expected = "SERVER_ERROR cache is at capacity\r\n"
+        .to_string()
+        .into_bytes();
+    assert_eq!(transport.get_stream().outgoing, expected);
+}
+
+#[test]
+fn test_write_resp_ok() {
+    let ts = TestStream::new(vec![]);
+    let mut transport = TcpTransport::new(ts);
+
+    transport.write_resp(&Resp::Ok).unwrap();
+    let expected = "OK\r\n".to_string().into_bytes();
+    assert_eq!(transport.get_stream().outgoing, expected);
 }
 
 
@@ -250,6 +540,205 @@ fn test_read_cmd_stats() {
 }
 
 
+// Command parsing: binary protocol
+
+#[test]
+fn test_read_cmd_binary_get_ok() {
+    let mut cmd_bytes = vec![
+        0x80, 0x00, // magic, opcode (Get)
+        0x00, 0x01, // key length
+        0x00, // extras length
+        0x00, // data type
+        0x00, 0x00, // vbucket id
+        0x00, 0x00, 0x00, 0x01, // total body length
+        0x00, 0x00, 0x00, 0x00, // opaque
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // cas
+    ];
+    cmd_bytes.extend_from_slice(b"x");
+    let ts = TestStream::new(cmd_bytes);
+    let mut transport = TcpTransport::new(ts);
+
+    let cmd = transport.read_cmd().unwrap();
+    assert_eq!(cmd, Cmd::Get(Get::new("x")));
+}
+
+#[test]
+fn test_read_cmd_binary_set_ok() {
+    let mut cmd_bytes = vec![
+        0x80, 0x01, // magic, opcode (Set)
+        0x00, 0x01, // key length
+        0x08, // extras length
+        0x00, // data type
+        0x00, 0x00, // vbucket id
+        0x00, 0x00, 0x00, 0x0c, // total body length (8 extras + 1 key + 3 value)
+        0x00, 0x00, 0x00, 0x00, // opaque
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // cas
+        0x00, 0x00, 0x00, 0x00, // extras: flags
+        0x00, 0x00, 0x00, 0x00, // extras: expiry
+    ];
+    cmd_bytes.extend_from_slice(b"x");
+    cmd_bytes.extend_from_slice(b"abc");
+    let ts = TestStream::new(cmd_bytes);
+    let mut transport = TcpTransport::new(ts);
+
+    let cmd = transport.read_cmd().unwrap();
+    assert_eq!(cmd, Cmd::Set(Set::new("x", 0, vec![97, 98, 99])));
+}
+
+#[test]
+fn test_read_cmd_binary_unknown_opcode() {
+    let cmd_bytes = vec![
+        0x80, 0xff, // magic, unknown opcode
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ];
+    let ts = TestStream::new(cmd_bytes);
+    let mut transport = TcpTransport::new(ts);
+
+    let err = transport.read_cmd().unwrap_err();
+    assert_eq!(err, Error::InvalidCommand { verb: Verb::new(&[0xff]) });
+}
+
+#[test]
+fn test_read_cmd_binary_declared_length_overflow_rejected() {
+    let cmd_bytes = vec![
+        0x80, 0x00, // magic, opcode (Get)
+        0x00, 0x01, // key length
+        0x00, // extras length
+        0x00, // data type
+        0x00, 0x00, // vbucket id
+        0xff, 0xff, 0xff, 0xff, // total body length (~4.3 GB)
+        0x00, 0x00, 0x00, 0x00, // opaque
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // cas
+    ];
+    let ts = TestStream::new(cmd_bytes);
+    let mut transport = TcpTransport::new(ts);
+
+    let err = transport.read_cmd().unwrap_err();
+    assert_eq!(err, Error::ValueTooLong { limit: DATA_MAXLEN as u64, got: 0xffffffff });
+}
+
+#[test]
+fn test_read_cmd_binary_delete_ok() {
+    let mut cmd_bytes = vec![
+        0x80, 0x04, // magic, opcode (Delete)
+        0x00, 0x01, // key length
+        0x00, // extras length
+        0x00, // data type
+        0x00, 0x00, // vbucket id
+        0x00, 0x00, 0x00, 0x01, // total body length
+        0x00, 0x00, 0x00, 0x00, // opaque
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // cas
+    ];
+    cmd_bytes.extend_from_slice(b"x");
+    let ts = TestStream::new(cmd_bytes);
+    let mut transport = TcpTransport::new(ts);
+
+    let cmd = transport.read_cmd().unwrap();
+    assert_eq!(cmd, Cmd::Delete(Delete::new("x")));
+}
+
+#[test]
+fn test_read_cmd_binary_incr_ok() {
+    let mut cmd_bytes = vec![
+        0x80, 0x05, // magic, opcode (Increment)
+        0x00, 0x01, // key length
+        0x14, // extras length (20 bytes: delta, initial, expiration)
+        0x00, // data type
+        0x00, 0x00, // vbucket id
+        0x00, 0x00, 0x00, 0x15, // total body length (20 extras + 1 key)
+        0x00, 0x00, 0x00, 0x00, // opaque
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // cas
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05, // extras: delta = 5
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // extras: initial
+        0x00, 0x00, 0x00, 0x00, // extras: expiration
+    ];
+    cmd_bytes.extend_from_slice(b"x");
+    let ts = TestStream::new(cmd_bytes);
+    let mut transport = TcpTransport::new(ts);
+
+    let cmd = transport.read_cmd().unwrap();
+    assert_eq!(cmd, Cmd::Incr(IncrDecr::new("x", 5)));
+}
+
+#[test]
+fn test_write_resp_binary_number() {
+    let ts = TestStream::new(vec![]);
+    let mut transport = TcpTransport::new(ts);
+
+    transport.write_resp_binary(&Resp::Number(5), 0x05, [0, 0, 0, 0]).unwrap();
+
+    let mut expected = vec![
+        0x81, 0x05, // magic, opcode
+        0x00, 0x00, // key length
+        0x00, // extras length
+        0x00, // data type
+        0x00, 0x00, // status (ok)
+        0x00, 0x00, 0x00, 0x08, // total body length (8-byte numeric value)
+        0x00, 0x00, 0x00, 0x00, // opaque
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // cas
+    ];
+    expected.extend_from_slice(&[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05]);
+    assert_eq!(transport.get_stream().outgoing, expected);
+}
+
+#[test]
+fn test_read_cmd_text_mode_unaffected_by_binary_peek() {
+    // Sanity check that peeking the first byte to pick a protocol doesn't
+    // disturb the stream for ordinary ASCII commands.
+    let cmd_str = "get x\r\n".to_string();
+    let ts = TestStream::new(cmd_str.into_bytes());
+    let mut transport = TcpTransport::new(ts);
+
+    let cmd = transport.read_cmd().unwrap();
+    assert_eq!(cmd, Cmd::Get(Get::new("x")));
+}
+
+
+// Response writing: binary protocol
+
+#[test]
+fn test_write_resp_binary_value() {
+    let ts = TestStream::new(vec![]);
+    let mut transport = TcpTransport::new(ts);
+
+    let resp = Resp::Value(Value::new("x", "abc".to_string().into_bytes()));
+    transport.write_resp_binary(&resp, 0x00, [1, 2, 3, 4]).unwrap();
+
+    let mut expected = vec![
+        0x81, 0x00, // magic, opcode (echoed)
+        0x00, 0x00, // key length (responses omit the key)
+        0x04, // extras length
+        0x00, // data type
+        0x00, 0x00, // status (ok)
+        0x00, 0x00, 0x00, 0x07, // total body length (4 extras + 3 value)
+        0x01, 0x02, 0x03, 0x04, // opaque (echoed)
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // cas
+        0x00, 0x00, 0x00, 0x00, // extras: flags
+    ];
+    expected.extend_from_slice(b"abc");
+    assert_eq!(transport.get_stream().outgoing, expected);
+}
+
+#[test]
+fn test_write_resp_binary_error() {
+    let ts = TestStream::new(vec![]);
+    let mut transport = TcpTransport::new(ts);
+
+    let resp = Resp::Error;
+    transport.write_resp_binary(&resp, 0x00, [0, 0, 0, 0]).unwrap();
+
+    let expected = vec![
+        0x81, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x84, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00,
+    ];
+    assert_eq!(transport.get_stream().outgoing, expected);
+}
+
+
 // Response writing: Error
 
 #[test]